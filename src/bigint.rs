@@ -0,0 +1,160 @@
+//! Integers of any size, with the `bigint` feature.
+//!
+//! ```
+//! use serde_jacl::{de::from_str, ser::to_string, JaclBigInt};
+//!
+//! let int: JaclBigInt = from_str("-123456789012345678901234567890").unwrap();
+//! assert_eq!("-123456789012345678901234567890", int.0.to_string());
+//! assert_eq!("-123456789012345678901234567890", to_string(&int).unwrap());
+//! ```
+
+use num_bigint::BigInt;
+use serde::{
+    de::{self, Deserialize, Deserializer, Visitor},
+    Serialize, Serializer,
+};
+use std::fmt;
+
+// `JaclBigInt` (de)serializes as a newtype struct with this name, which the
+// JACL deserializer reads as the digits of an integer of any size and the
+// serializer writes as a bare integer. Other formats see an ordinary newtype
+// around an integer or a string.
+pub(crate) const BIGINT_NAME: &str = "$serde_jacl::private::BigInt";
+
+/// An integer too large for `i128` or `u128`, read from JACL like any other
+/// integer, including the `0x`, `0o` and `0b` forms. Use
+/// [`BigInt::to_biguint`] for an unsigned value.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JaclBigInt(pub BigInt);
+
+impl<'de> Deserialize<'de> for JaclBigInt {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(BIGINT_NAME, BigIntVisitor)
+    }
+}
+
+// the digits are written as a string, since serde has no integer type of
+// any size
+impl Serialize for JaclBigInt {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(BIGINT_NAME, &self.0.to_string())
+    }
+}
+
+struct BigIntVisitor;
+
+impl<'de> Visitor<'de> for BigIntVisitor {
+    type Value = JaclBigInt;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<JaclBigInt, E> {
+        Ok(JaclBigInt(v.into()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<JaclBigInt, E> {
+        Ok(JaclBigInt(v.into()))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<JaclBigInt, E> {
+        Ok(JaclBigInt(v.into()))
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<JaclBigInt, E> {
+        Ok(JaclBigInt(v.into()))
+    }
+
+    // the digits, from the JACL deserializer or a format that keeps large
+    // integers as strings
+    fn visit_str<E>(self, v: &str) -> Result<JaclBigInt, E>
+    where
+        E: de::Error,
+    {
+        v.parse()
+            .map(JaclBigInt)
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<JaclBigInt, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::from_str;
+    use std::str::FromStr;
+
+    fn big(s: &str) -> JaclBigInt {
+        JaclBigInt(BigInt::from_str(s).unwrap())
+    }
+
+    #[test]
+    fn test_large_values() {
+        let s = "123456789012345678901234567890123456789012345678901234567890";
+        assert_eq!(big(s), from_str::<JaclBigInt>(s).unwrap());
+        assert_eq!(
+            big(&format!("-{}", s)),
+            from_str(format!("-{}", s)).unwrap()
+        );
+        assert_eq!(big(s), from_str(format!("+{}", s)).unwrap());
+        assert_eq!(big("42"), from_str::<JaclBigInt>("42").unwrap());
+        assert_eq!(
+            big("340282366920938463463374607431768211456"),
+            from_str::<JaclBigInt>("0x1_0000_0000_0000_0000_0000_0000_0000_0000").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fields() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Config {
+            balance: JaclBigInt,
+            history: Vec<JaclBigInt>,
+        }
+        let config: Config =
+            from_str("balance : -99999999999999999999999999999999999999999 history : [1 -2]")
+                .unwrap();
+        assert_eq!(
+            big("-99999999999999999999999999999999999999999"),
+            config.balance
+        );
+        assert_eq!(vec![big("1"), big("-2")], config.history);
+
+        assert!(from_str::<Config>("balance : 1.5 history : []").is_err());
+        assert!(from_str::<Config>(r#"balance : "1" history : []"#).is_err());
+    }
+
+    #[test]
+    fn test_serialize() {
+        use crate::ser::to_string;
+        let s = "-123456789012345678901234567890123456789012345678901234567890";
+        assert_eq!(s, to_string(&big(s)).unwrap());
+        assert_eq!(big(s), from_str(to_string(&big(s)).unwrap()).unwrap());
+        assert_eq!("[1 -2]", to_string(&vec![big("1"), big("-2")]).unwrap());
+        // other formats get the digits as a string
+        #[cfg(feature = "json")]
+        assert_eq!("\"42\"", serde_json::to_string(&big("42")).unwrap());
+    }
+
+    #[test]
+    fn test_other_deserializers() {
+        use serde::de::{value::Error, IntoDeserializer};
+        let int = JaclBigInt::deserialize(IntoDeserializer::<Error>::into_deserializer(-5i64));
+        assert_eq!(big("-5"), int.unwrap());
+        let int = JaclBigInt::deserialize(IntoDeserializer::<Error>::into_deserializer(u128::MAX));
+        assert_eq!(big(&u128::MAX.to_string()), int.unwrap());
+    }
+}
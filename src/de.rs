@@ -1,67 +1,290 @@
 use std::fmt;
-use std::{error, fmt::{Debug, Display}, str::FromStr};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    error,
+    fmt::{Debug, Display},
+    fs,
+    io::{self, Read},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
 
-use crate::parsing;
+use crate::parsing::{
+    self,
+    string::{InterpolateError, StringError},
+};
 use nom::{branch::alt, multi::many0};
 use num::{Float, Integer};
-use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor, DeserializeOwned};
+use serde::de::{
+    self,
+    value::{BorrowedStrDeserializer, CowStrDeserializer, SeqDeserializer},
+    Deserialize, DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, Unexpected,
+    VariantAccess, Visitor,
+};
 
-
-#[derive(Eq, PartialEq)]
+#[derive(Clone, Copy, Eq, PartialEq)]
 enum DataType {
-    STRUCT,
-    HASHMAP,
-    SEQ,
+    Struct,
+    HashMap,
+    Seq,
+}
+
+// what `skip_value` reads next
+#[derive(Clone, Copy)]
+enum Skip {
+    // an element or entry of the innermost container, or its closer
+    Item,
+    // the `:` after a key
+    Separator,
+    // the value after a `:` or an anchor
+    Value,
+}
+
+/// What went wrong while deserializing.
+#[derive(Debug, Clone)]
+pub enum ErrorKind {
+    /// The input isn't valid JACL, or doesn't match the type being deserialized.
+    Syntax,
+    /// A `Deserialize` impl rejected the input through `de::Error::custom`.
+    Custom(String),
+    /// Reading the input failed. Shared so that errors can be cloned.
+    Io(Arc<io::Error>),
+    /// The input bytes aren't valid UTF-8, starting at this byte offset.
+    InvalidUtf8(usize),
+    /// Containers are nested deeper than the deserializer's maximum depth.
+    DepthLimit(usize),
+    /// Anchor references expand to more than this many bytes in total.
+    ExpansionLimit(usize),
+}
+
+// io errors aren't comparable, so they're equal when their kinds are
+impl PartialEq for ErrorKind {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ErrorKind::Syntax, ErrorKind::Syntax) => true,
+            (ErrorKind::Custom(a), ErrorKind::Custom(b)) => a == b,
+            (ErrorKind::Io(a), ErrorKind::Io(b)) => a.kind() == b.kind(),
+            (ErrorKind::InvalidUtf8(a), ErrorKind::InvalidUtf8(b)) => a == b,
+            (ErrorKind::DepthLimit(a), ErrorKind::DepthLimit(b)) => a == b,
+            (ErrorKind::ExpansionLimit(a), ErrorKind::ExpansionLimit(b)) => a == b,
+            _ => false,
+        }
+    }
 }
 
+/// A deserialization error, along with where in the input it happened.
+///
+/// ```
+/// use serde_jacl::de::from_str;
+///
+/// let err = from_str::<Vec<u32>>("[1 2\n 3 x]").unwrap_err();
+/// assert_eq!((2, 3), (err.line(), err.col()));
+/// assert_eq!(" 3 x]", err.line_str());
+/// assert_eq!(Some("expected an integer, found `x`"), err.hint());
+/// ```
+#[derive(Clone, PartialEq)]
 pub struct JaclDeError {
+    kind: ErrorKind,
     col: usize,
     line: usize,
     line_str: String,
+    // what the parser was looking for and what it found instead
+    hint: Option<String>,
+    // the file the location is in, for errors from `from_path`
+    file: Option<PathBuf>,
 }
 
 impl JaclDeError {
     pub fn new(d: &Deserializer) -> Self {
-        let index = d
-            .begin
-            .rfind(d.input)
-            .expect("There's a bug in the parser!");
-
-        let mut curr = 0;
-        let mut col = 0;
-        let mut line = 1;
-
-        for c in d.begin.chars() {
-            if curr == index {
-                break;
-            }
-            if c == '\n' {
-                line += 1;
-                col = 0;
-            } else {
-                col += 1;
-            }
-            curr += 1
-        }
+        JaclDeError::spanned(d, ErrorKind::Syntax)
+    }
 
-        let mut curr = 0;
-        let mut line_str = "".to_string();
-        for c in d.begin.chars() {
-            if curr >= index - col {
-                line_str = format!("{}{}", line_str, c);
-                if c == '\n' {
-                    break;
-                }
-            }
-            curr += 1
-        }
+    /// A syntax error at the deserializer's current location, noting that
+    /// `expected` (e.g. "`:`" or "a string") should have been there.
+    pub fn expected(d: &Deserializer, expected: &str) -> Self {
+        let hint = format!("expected {}, found {}", expected, describe(d.input, d.post));
+        JaclDeError::hinted(d, hint)
+    }
+
+    // like `expected`, for parsers that track their own offset into `input`
+    pub(crate) fn expected_at(input: &str, offset: usize, expected: &str) -> Self {
+        let hint = format!(
+            "expected {}, found {}",
+            expected,
+            describe(&input[offset..], None)
+        );
+        JaclDeError::hinted_at(input, offset, hint)
+    }
+
+    fn hinted_at(input: &str, offset: usize, hint: String) -> Self {
+        let mut err = JaclDeError::at(input, offset, ErrorKind::Syntax);
+        err.hint = Some(hint);
+        err
+    }
+
+    // an error of any kind, for parsers that track their own offset
+    pub(crate) fn at(input: &str, offset: usize, kind: ErrorKind) -> Self {
+        JaclDeError::located(input, &line_starts(input), offset, kind)
+    }
+
+    fn hinted(d: &Deserializer, hint: String) -> Self {
+        let mut err = JaclDeError::new(d);
+        err.hint = Some(hint);
+        err
+    }
+
+    // errors that point at the deserializer's current location
+    fn spanned(d: &Deserializer, kind: ErrorKind) -> Self {
+        // `input` is always a suffix of `begin`
+        let offset = d.input.as_ptr() as usize - d.begin.as_ptr() as usize;
+        JaclDeError::located(d.begin, &d.line_starts(), offset, kind)
+    }
+
+    fn located(begin: &str, line_starts: &[usize], offset: usize, kind: ErrorKind) -> Self {
+        let line = line_starts.partition_point(|&start| start <= offset);
+        let line_start = line_starts[line - 1];
+        let col = begin[line_start..offset].chars().count();
+        // the line ending, `\n` or `\r\n`, isn't part of the line
+        let line_end = match begin[line_start..].find('\n') {
+            Some(end) => line_start + end,
+            None => begin.len(),
+        };
+        let line_end = if begin[line_start..line_end].ends_with('\r') {
+            line_end - 1
+        } else {
+            line_end
+        };
 
         JaclDeError {
+            kind,
             col,
             line,
-            line_str,
+            line_str: begin[line_start..line_end].to_string(),
+            hint: None,
+            file: None,
         }
     }
+
+    // errors that don't point at a location in the input
+    fn without_span(kind: ErrorKind) -> Self {
+        JaclDeError {
+            kind,
+            col: 0,
+            line: 0,
+            line_str: "".to_string(),
+            hint: None,
+            file: None,
+        }
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// The line the error is on, starting from 1. 0 if the error has no location.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column the error is at in chars, starting from 0.
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    /// The text of the line the error is on.
+    pub fn line_str(&self) -> &str {
+        &self.line_str
+    }
+
+    /// What was expected at the error's location, if known.
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+
+    /// The file the error is in, for errors from [`from_path`]. The line and
+    /// column are in this file rather than in the text with includes spliced
+    /// in.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    // ` in <file>` for errors from `from_path`, to follow "error"
+    fn in_file(&self) -> String {
+        match &self.file {
+            Some(file) => format!(" in {}", file.display()),
+            None => String::new(),
+        }
+    }
+
+    /// Renders the error with the lines around it from `src`, the input that
+    /// was being deserialized, each with its line number in a gutter:
+    ///
+    /// ```text
+    /// error at line: 2 col: 3
+    /// 1 | [1 2
+    /// 2 |  3 x
+    ///   |    ^
+    /// 3 |  4]
+    /// expected an integer, found `x`
+    /// ```
+    ///
+    /// Errors without a location render the same as with `Display`. For
+    /// errors from [`from_path`], `src` should be the contents of
+    /// [`file`](JaclDeError::file).
+    pub fn render_with_context(&self, src: &str) -> String {
+        if self.line == 0 {
+            return self.to_string();
+        }
+        let mut out = match &self.kind {
+            ErrorKind::DepthLimit(max) => format!(
+                "error: nested deeper than {} levels{} at line: {} col: {}\n",
+                max,
+                self.in_file(),
+                self.line,
+                self.col
+            ),
+            ErrorKind::ExpansionLimit(max) => format!(
+                "error: anchors expand to more than {} bytes{} at line: {} col: {}\n",
+                max,
+                self.in_file(),
+                self.line,
+                self.col
+            ),
+            _ => format!(
+                "error{} at line: {} col: {}\n",
+                self.in_file(),
+                self.line,
+                self.col
+            ),
+        };
+        let lines: Vec<&str> = src.lines().collect();
+        let first = self.line.saturating_sub(1).max(1);
+        let last = (self.line + 1).min(lines.len().max(self.line));
+        let width = last.to_string().len();
+        for line in first..=last {
+            let text = lines.get(line - 1).copied().unwrap_or("");
+            let row = format!("{:>width$} | {}", line, text, width = width);
+            out.push_str(row.trim_end());
+            out.push('\n');
+            if line == self.line {
+                out.push_str(&format!(
+                    "{:>width$} | {}^\n",
+                    "",
+                    " ".repeat(self.col),
+                    width = width
+                ));
+            }
+        }
+        if let Some(hint) = &self.hint {
+            out.push_str(hint);
+            out.push('\n');
+        }
+        out
+    }
 }
 
 impl Debug for JaclDeError {
@@ -72,32 +295,256 @@ impl Debug for JaclDeError {
 
 impl Display for JaclDeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        let marker_str = format!("{}^\n", "-".repeat(self.col));
-        write!(
-            f,
-            "error at line: {} col: {}\n{}\n{}",
-            self.line, self.col, self.line_str, marker_str
-        )?;
+        match &self.kind {
+            ErrorKind::Syntax => {
+                let marker_str = format!("{}^\n", "-".repeat(self.col));
+                write!(
+                    f,
+                    "error{} at line: {} col: {}\n{}\n{}",
+                    self.in_file(),
+                    self.line,
+                    self.col,
+                    self.line_str,
+                    marker_str
+                )?;
+                if let Some(hint) = &self.hint {
+                    writeln!(f, "{}", hint)?;
+                }
+            }
+            ErrorKind::Custom(msg) => write!(f, "error: {}", msg)?,
+            ErrorKind::Io(err) => write!(f, "io error: {}", err)?,
+            ErrorKind::InvalidUtf8(offset) => {
+                write!(f, "error: invalid utf-8 at byte offset {}", offset)?
+            }
+            ErrorKind::DepthLimit(max) => write!(
+                f,
+                "error: nested deeper than {} levels{} at line: {} col: {}",
+                max,
+                self.in_file(),
+                self.line,
+                self.col
+            )?,
+            ErrorKind::ExpansionLimit(max) => write!(
+                f,
+                "error: anchors expand to more than {} bytes{} at line: {} col: {}",
+                max,
+                self.in_file(),
+                self.line,
+                self.col
+            )?,
+        }
         Ok(())
     }
 }
 
 impl error::Error for JaclDeError {}
 
+// what a container opened with `open` is called in errors
+fn container_kind(open: char) -> Option<&'static str> {
+    match open {
+        '(' => Some("struct"),
+        '{' => Some("map"),
+        '[' => Some("seq"),
+        _ => None,
+    }
+}
+
+// the text of an unquoted key at the start of `input`, which ends before the
+// `=` of `with_equals_separator` too
+fn key_token(input: &str) -> &str {
+    let end = input
+        .find(|c: char| c.is_whitespace() || c == ',' || c == '=' || parsing::is_delimiter(c))
+        .unwrap_or(input.len());
+    &input[..end]
+}
+
+// the byte offset each line of `input` starts at
+fn line_starts(input: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+// the input after a string, literal or bare word at the start of `input`,
+// checked but not decoded
+fn scalar_end(input: &str) -> Option<&str> {
+    if input.starts_with('"') {
+        return parsing::string::string(input).ok().map(|(rest, _)| rest);
+    }
+    let end = input
+        .find(|c: char| c.is_whitespace() || parsing::is_delimiter(c) || ",=\"/#".contains(c))
+        .unwrap_or(input.len());
+    let (word, rest) = input.split_at(end);
+    let is_whole = |parsed: Option<&str>| parsed == Some("");
+    let is_scalar = is_whole(parsing::identifier(word).ok().map(|r| r.0))
+        || is_whole(parsing::literal::float::<f64>(word).ok().map(|r| r.0))
+        || is_whole(parsing::literal::integer::<i128>(word).ok().map(|r| r.0))
+        || is_whole(parsing::literal::decimal_digits(word).ok().map(|r| r.0));
+    if is_scalar && !word.is_empty() {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+// names the token at the start of `input` for error hints, or `post` if
+// there's nothing left
+fn describe(input: &str, post: Option<char>) -> String {
+    match input.chars().next() {
+        None => match post {
+            Some(post) => format!("`{}`", post),
+            None => "end of input".to_string(),
+        },
+        Some('"') => "a string".to_string(),
+        Some(c) if c.is_alphanumeric() || c == '_' || c == '-' || c == '+' => {
+            let len = input[1..]
+                .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '.')
+                .map_or(input.len(), |len| len + 1);
+            format!("`{}`", &input[..len])
+        }
+        Some(c) => format!("`{}`", c),
+    }
+}
+
+impl From<io::Error> for JaclDeError {
+    fn from(err: io::Error) -> Self {
+        JaclDeError::without_span(ErrorKind::Io(Arc::new(err)))
+    }
+}
+
 impl de::Error for JaclDeError {
-    fn custom<T>(_: T) -> Self
+    fn custom<T>(msg: T) -> Self
     where
         T: std::fmt::Display,
     {
-        unreachable!();
+        JaclDeError::without_span(ErrorKind::Custom(msg.to_string()))
+    }
+}
+
+/// How deeply containers may nest before deserializing fails, unless
+/// overridden with [`Deserializer::with_max_depth`].
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+// References to anchors may expand to this many times the length of the
+// input, or to `MIN_EXPANSION` bytes if that's more.
+const EXPANSION_FACTOR: usize = 64;
+const MIN_EXPANSION: usize = 1 << 20;
+
+/// Parsing options for [`from_str_with_options`], set together rather than
+/// one by one on a [`Deserializer`]. The flags are off by default and the
+/// depth limit starts at [`DEFAULT_MAX_DEPTH`].
+#[derive(Clone, Debug)]
+pub struct DeOptions {
+    max_depth: usize,
+    equals_separator: bool,
+    strict_commas: bool,
+    deny_unknown_fields: bool,
+    deny_duplicate_keys: bool,
+    env_interpolation: bool,
+    anchors: bool,
+}
+
+impl Default for DeOptions {
+    fn default() -> Self {
+        DeOptions {
+            max_depth: DEFAULT_MAX_DEPTH,
+            equals_separator: false,
+            strict_commas: false,
+            deny_unknown_fields: false,
+            deny_duplicate_keys: false,
+            env_interpolation: false,
+            anchors: false,
+        }
+    }
+}
+
+impl DeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [`Deserializer::with_max_depth`]. Defaults to [`DEFAULT_MAX_DEPTH`].
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// See [`Deserializer::with_equals_separator`].
+    pub fn with_equals_separator(mut self, equals_separator: bool) -> Self {
+        self.equals_separator = equals_separator;
+        self
+    }
+
+    /// See [`Deserializer::with_strict_commas`].
+    pub fn with_strict_commas(mut self, strict_commas: bool) -> Self {
+        self.strict_commas = strict_commas;
+        self
+    }
+
+    /// See [`Deserializer::with_deny_unknown_fields`].
+    pub fn with_deny_unknown_fields(mut self, deny_unknown_fields: bool) -> Self {
+        self.deny_unknown_fields = deny_unknown_fields;
+        self
+    }
+
+    /// See [`Deserializer::with_deny_duplicate_keys`].
+    pub fn with_deny_duplicate_keys(mut self, deny_duplicate_keys: bool) -> Self {
+        self.deny_duplicate_keys = deny_duplicate_keys;
+        self
+    }
+
+    /// See [`Deserializer::with_env_interpolation`].
+    pub fn with_env_interpolation(mut self, env_interpolation: bool) -> Self {
+        self.env_interpolation = env_interpolation;
+        self
+    }
+
+    /// See [`Deserializer::with_anchors`].
+    pub fn with_anchors(mut self, anchors: bool) -> Self {
+        self.anchors = anchors;
+        self
     }
 }
 
+#[derive(Clone)]
 pub struct Deserializer<'de> {
     begin: &'de str,
     pre: Option<char>,
     input: &'de str,
     post: Option<char>,
+    depth: usize,
+    max_depth: usize,
+    equals_separator: bool,
+    strict_commas: bool,
+    deny_unknown_fields: bool,
+    deny_duplicate_keys: bool,
+    env_interpolation: bool,
+    // with `with_anchors`, the name and source of each anchored value in the
+    // order they were defined, or None when anchors are off. Only appended
+    // to, apart from the definitions read again while expanding a reference,
+    // so a checkpoint just needs its length.
+    anchors: Option<Vec<(&'de str, &'de str)>>,
+    // how many bytes of anchored values references have expanded to
+    expanded: usize,
+    // the offset of each open container's delimiter, innermost last, or None
+    // for the implicit top level one
+    opened: Vec<Option<usize>>,
+    // the offset of the last opening delimiter read, until its container is
+    // entered
+    last_open: Option<usize>,
+    // byte offsets where each line of `begin` starts, computed on the first error
+    line_starts: RefCell<Option<Arc<[usize]>>>,
+}
+
+/// A position in a [`Deserializer`]'s input, from [`Deserializer::save`].
+#[derive(Clone, Debug)]
+pub struct Checkpoint<'de> {
+    pre: Option<char>,
+    input: &'de str,
+    post: Option<char>,
+    depth: usize,
+    opened: Vec<Option<usize>>,
+    anchors: usize,
 }
 
 impl<'de> Deserializer<'de> {
@@ -107,75 +554,650 @@ impl<'de> Deserializer<'de> {
             post,
             begin: data,
             input: data,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            equals_separator: false,
+            strict_commas: false,
+            deny_unknown_fields: false,
+            deny_duplicate_keys: false,
+            env_interpolation: false,
+            anchors: None,
+            expanded: 0,
+            opened: Vec::new(),
+            last_open: None,
+            line_starts: RefCell::new(None),
+        }
+    }
+
+    /// Sets how deeply sequences, maps and structs may nest. Deeper input is
+    /// an error rather than a stack overflow.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Accepts `key = value` as well as `key : value` in maps and structs.
+    pub fn with_equals_separator(mut self, equals_separator: bool) -> Self {
+        self.equals_separator = equals_separator;
+        self
+    }
+
+    /// Parses commas as separators instead of whitespace. At most one comma
+    /// may come between elements or entries, and none before the first or
+    /// after the last, so `[1,,2]` and `[1, 2,]` are errors. Nor may one come
+    /// between a key and its `:`, as in `{"a", : 1}`. Elements separated only
+    /// by whitespace are still accepted.
+    pub fn with_strict_commas(mut self, strict_commas: bool) -> Self {
+        self.strict_commas = strict_commas;
+        self
+    }
+
+    /// Errors on a struct field that the struct being deserialized doesn't
+    /// have, rather than leaving it to the `Deserialize` impl, which usually
+    /// ignores it.
+    pub fn with_deny_unknown_fields(mut self, deny_unknown_fields: bool) -> Self {
+        self.deny_unknown_fields = deny_unknown_fields;
+        self
+    }
+
+    /// Errors on a map key or struct field that appears twice in the same
+    /// container, rather than keeping the last value.
+    pub fn with_deny_duplicate_keys(mut self, deny_duplicate_keys: bool) -> Self {
+        self.deny_duplicate_keys = deny_duplicate_keys;
+        self
+    }
+
+    /// Replaces `${VAR}` in strings with the environment variable `VAR`, so
+    /// that `"${HOME}/config"` is the path of a file in the home directory.
+    /// `$$` is a literal `$`. A variable that isn't set is an error.
+    pub fn with_env_interpolation(mut self, env_interpolation: bool) -> Self {
+        self.env_interpolation = env_interpolation;
+        self
+    }
+
+    /// Lets a value in a sequence, map or struct be named with `&name value`
+    /// and repeated elsewhere with `*name`, which reads as a copy of it:
+    /// `base : &defaults (port : 80) site : *defaults`. A reference must come
+    /// after the anchored value, outside of it.
+    ///
+    /// Each reference reads its anchored value again, so anchors that refer
+    /// to each other can expand exponentially. In total, references may
+    /// expand to 64 times the length of the input, or 1 MiB if that's more,
+    /// after which deserializing fails with [`ErrorKind::ExpansionLimit`].
+    pub fn with_anchors(mut self, anchors: bool) -> Self {
+        self.anchors = if anchors { Some(Vec::new()) } else { None };
+        self
+    }
+
+    /// Applies all of `options` at once.
+    pub fn with_options(self, options: &DeOptions) -> Self {
+        self.with_max_depth(options.max_depth)
+            .with_equals_separator(options.equals_separator)
+            .with_strict_commas(options.strict_commas)
+            .with_deny_unknown_fields(options.deny_unknown_fields)
+            .with_deny_duplicate_keys(options.deny_duplicate_keys)
+            .with_env_interpolation(options.env_interpolation)
+            .with_anchors(options.anchors)
+    }
+
+    /// Checks that nothing but whitespace and comments is left after the
+    /// value that was deserialized.
+    pub fn end(&mut self) -> Result<(), JaclDeError> {
+        self.skip_non_tokens()?;
+        // an unconsumed implicit closer means the input closed the container itself
+        if self.post.is_some() {
+            return Err(JaclDeError::new(self));
+        }
+        if !self.input.is_empty() {
+            return Err(JaclDeError::expected(self, "end of input"));
+        }
+        Ok(())
+    }
+
+    // a document of only whitespace and comments has no value to deserialize,
+    // whatever the type, so it's an error up front rather than wherever the
+    // type's parsing happens to fail
+    fn expect_value(&mut self) -> Result<(), JaclDeError> {
+        if self.pre.is_none() {
+            self.skip_non_tokens()?;
+            if self.input.is_empty() {
+                return Err(JaclDeError::expected(self, "a value"));
+            }
+        }
+        Ok(())
+    }
+
+    /// The input that hasn't been consumed yet.
+    pub fn remaining(&self) -> &'de str {
+        self.input
+    }
+
+    /// Records the current position, so that a `Deserialize` impl can try
+    /// reading the input one way and [`restore`](Deserializer::restore) it
+    /// to try another.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_jacl::de::Deserializer;
+    ///
+    /// let mut de = Deserializer::new(None, "\"42\"", None);
+    /// let checkpoint = de.save();
+    /// assert!(u32::deserialize(&mut de).is_err());
+    /// de.restore(checkpoint);
+    /// assert_eq!("42", String::deserialize(&mut de).unwrap());
+    /// ```
+    pub fn save(&self) -> Checkpoint<'de> {
+        Checkpoint {
+            pre: self.pre,
+            input: self.input,
+            post: self.post,
+            depth: self.depth,
+            opened: self.opened.clone(),
+            anchors: self.anchors.as_ref().map_or(0, Vec::len),
+        }
+    }
+
+    /// Goes back to a position from [`save`](Deserializer::save) on this
+    /// deserializer. Anchors defined after the checkpoint are forgotten, so
+    /// checkpoints saved after it shouldn't be restored afterwards.
+    pub fn restore(&mut self, checkpoint: Checkpoint<'de>) {
+        self.pre = checkpoint.pre;
+        self.input = checkpoint.input;
+        self.post = checkpoint.post;
+        self.depth = checkpoint.depth;
+        self.opened = checkpoint.opened;
+        if let Some(anchors) = &mut self.anchors {
+            anchors.truncate(checkpoint.anchors);
+        }
+    }
+
+    fn line_starts(&self) -> Arc<[usize]> {
+        self.line_starts
+            .borrow_mut()
+            .get_or_insert_with(|| line_starts(self.begin).into())
+            .clone()
+    }
+
+    // called on entering a container, paired with `leave`
+    fn enter(&mut self) -> Result<(), JaclDeError> {
+        if self.depth >= self.max_depth {
+            return Err(JaclDeError::spanned(
+                self,
+                ErrorKind::DepthLimit(self.max_depth),
+            ));
+        }
+        self.depth += 1;
+        self.opened.push(self.last_open.take());
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+        self.opened.pop();
+    }
+
+    // the implicit closer at the end of the input only closes the implicit
+    // container, not one opened inside it
+    fn in_explicit_container(&self) -> bool {
+        matches!(self.opened.last(), Some(Some(_)))
+    }
+
+    // running out of input inside a container points at the delimiter that
+    // opened it, since that's the one missing its closer
+    fn end_of_input(&self) -> JaclDeError {
+        match self.opened.last() {
+            Some(Some(offset)) => {
+                let open = &self.begin[*offset..(*offset + 1)];
+                let mut err = JaclDeError::located(
+                    self.begin,
+                    &self.line_starts(),
+                    *offset,
+                    ErrorKind::Syntax,
+                );
+                err.hint = Some(format!("unclosed `{}`", open));
+                err
+            }
+            _ => JaclDeError::new(self),
         }
     }
 }
 
 impl<'de> Deserializer<'de> {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &'de str) -> Self {
         // handling implicit cases for []: arrays {}: maps (): structs
         let mut d = Deserializer::new(None, input, None);
+        // words like `inf` and `nan` are both float literals and identifiers,
+        // so a leading `inf :` is a struct key rather than a map key
+        if let Ok(ident) = d.parse_identifier() {
+            if parsing::literal::float::<f64>(ident).is_ok() && matches!(d.parse_delim(), Ok(':')) {
+                return Deserializer::new(Some('('), input, Some(')'));
+            }
+        }
+        let mut d = Deserializer::new(None, input, None);
         if d.try_parse_literal() {
             if d.try_parse_literal() {
                 return Deserializer::new(Some('['), input, Some(']'));
+            } else if d.input.starts_with('=') {
+                // only valid with `with_equals_separator`, which is set later
+                return Deserializer::new(Some('{'), input, Some('}'));
             } else if let Ok(delim) = d.parse_delim() {
                 if delim == ':' {
                     return Deserializer::new(Some('{'), input, Some('}'));
                 }
             }
-        } else if let Ok(_) = d.parse_identifier() {
+        } else if d.parse_identifier().is_ok() {
             return Deserializer::new(Some('('), input, Some(')'));
         }
-        return Deserializer::new(None, input, None);
+        Deserializer::new(None, input, None)
+    }
+
+    // the container that `from_str` wraps the input in, if any
+    pub(crate) fn implicit_open(&self) -> Option<char> {
+        self.pre
     }
 
     fn try_parse_literal(&mut self) -> bool {
-        if let Ok(_) = self.parse_bool() {
+        if self.parse_bool().is_ok() {
             return true;
         }
-        if let Ok(_) = self.parse_float::<f32>() {
+        if self.parse_float::<f32>().is_ok() {
             return true;
         }
-        if let Ok(_) = self.parse_int::<i64>() {
+        if self.parse_int::<i64>().is_ok() {
             return true;
         }
-        if let Ok(_) = self.parse_string() {
+        if self.parse_string().is_ok() {
             return true;
         }
-        if let Ok(_) = self.parse_null() {
+        if self.parse_null().is_ok() {
             return true;
         }
-        return false;
+        false
     }
 }
 
+/// Deserializes a `T` from a JACL document.
+///
+/// Types that serde reads from strings, like `String`, [`IpAddr`] and
+/// [`SocketAddr`], need quoted values, e.g. `addr : "127.0.0.1:8080"`. A bare
+/// word is only read as a string where the type doesn't say what to expect,
+/// as with [`Value`](crate::structs::Value).
+///
+/// When a key is repeated in a map, the last entry wins in a `HashMap`. To
+/// keep every entry, read the map as a `Vec<(String, T)>`. This works for a
+/// map in `{ }`, a struct in `( )` with bare keys, and a whole document of
+/// `key : value` entries:
+///
+/// ```
+/// use serde_jacl::de::from_str;
+///
+/// let s = r#"
+///     header : "Accept: */*"
+///     header : "X-Id: 7"
+/// "#;
+/// let headers: Vec<(String, String)> = from_str(s).unwrap();
+/// assert_eq!(
+///     vec![
+///         ("header".to_string(), "Accept: */*".to_string()),
+///         ("header".to_string(), "X-Id: 7".to_string()),
+///     ],
+///     headers
+/// );
+/// let s = r#"{"header" : "Accept: */*" "header" : "X-Id: 7"}"#;
+/// assert_eq!(headers, from_str::<Vec<(String, String)>>(s).unwrap());
+/// ```
+///
+/// [`IpAddr`]: std::net::IpAddr
+/// [`SocketAddr`]: std::net::SocketAddr
 pub fn from_str<T>(s: impl Into<String>) -> Result<T, JaclDeError>
 where
     T: DeserializeOwned,
 {
-    let s : String = s.into();
-    let mut deserializer = Deserializer::from_str(&s);
+    from_str_with_depth(s, DEFAULT_MAX_DEPTH)
+}
+
+/// Like [`from_str`], but allows containers to nest `max_depth` levels deep.
+pub fn from_str_with_depth<T>(s: impl Into<String>, max_depth: usize) -> Result<T, JaclDeError>
+where
+    T: DeserializeOwned,
+{
+    from_str_with_options(s, &DeOptions::new().with_max_depth(max_depth))
+}
+
+/// Like [`from_str`], but parsed according to `options`.
+pub fn from_str_with_options<T>(s: impl Into<String>, options: &DeOptions) -> Result<T, JaclDeError>
+where
+    T: DeserializeOwned,
+{
+    let s: String = s.into();
+    let mut deserializer = Deserializer::from_str(&s).with_options(options);
+    deserializer.expect_value()?;
     let t = T::deserialize(&mut deserializer)?;
-    if deserializer.input.is_empty() {
-        Ok(t)
-    } else {
-        Err(JaclDeError::new(&deserializer))
+    deserializer.end()?;
+    Ok(t)
+}
+
+/// Like [`from_str`], but borrows from `s` rather than taking ownership of
+/// it, so that `T` can have `&str` fields pointing into the input. A string
+/// with escapes can't be borrowed, so reading one into a `&str` is an error.
+pub fn from_borrowed_str<'de, T>(s: &'de str) -> Result<T, JaclDeError>
+where
+    T: Deserialize<'de>,
+{
+    let mut deserializer = Deserializer::from_str(s);
+    deserializer.expect_value()?;
+    let t = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(t)
+}
+
+/// Like [`from_str`], but always reads the top level as the fields of a
+/// struct without parentheses, e.g. a config file of `name : "x"` lines.
+/// [`from_str`] has to guess, and reads a document starting with a quoted
+/// key or a number as a map.
+pub fn from_str_struct<T>(s: impl Into<String>) -> Result<T, JaclDeError>
+where
+    T: DeserializeOwned,
+{
+    let s: String = s.into();
+    let mut deserializer = Deserializer::new(Some('('), &s, Some(')'));
+    let t = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(t)
+}
+
+/// Deserializes one value from the start of `s` and returns it along with the
+/// rest of the input, so that concatenated values can be read one at a time.
+///
+/// As with [`from_str`], a value that starts with bare entries like `id : 1`
+/// is implicitly wrapped in a struct or map. That container has no closer, so
+/// it runs to the end of the input and the rest is empty; only the first
+/// value can be written this way. Literals aren't wrapped into a sequence,
+/// so `1 2` reads `1` and leaves `2`.
+pub fn from_str_remaining<'a, T>(s: &'a str) -> Result<(T, &'a str), JaclDeError>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer = Deserializer::from_str(s);
+    if deserializer.pre == Some('[') {
+        deserializer = Deserializer::new(None, s, None);
     }
+    deserializer.expect_value()?;
+    let t = T::deserialize(&mut deserializer)?;
+    if deserializer.pre.is_some() {
+        deserializer.end()?;
+    }
+    deserializer.skip_non_tokens()?;
+    Ok((t, deserializer.input))
+}
+
+/// Like [`from_str_remaining`], but returns how many bytes of `s` were
+/// consumed, including any whitespace and comments after the value.
+pub fn from_prefix<'a, T>(s: &'a str) -> Result<(T, usize), JaclDeError>
+where
+    T: Deserialize<'a>,
+{
+    let (t, rest) = from_str_remaining(s)?;
+    Ok((t, s.len() - rest.len()))
+}
+
+/// Iterates over the values in a document of concatenated values, separated
+/// by whitespace or comments. Values aren't implicitly wrapped, so each has to
+/// be a literal or a delimited container. Iteration stops after the first
+/// error.
+pub struct StreamDeserializer<'de, T> {
+    de: Deserializer<'de>,
+    failed: bool,
+    output: PhantomData<T>,
+}
+
+impl<'de, T> StreamDeserializer<'de, T> {
+    pub fn new(input: &'de str) -> Self {
+        StreamDeserializer {
+            de: Deserializer::new(None, input, None),
+            failed: false,
+            output: PhantomData,
+        }
+    }
+}
+
+impl<'de, T> Iterator for StreamDeserializer<'de, T>
+where
+    T: Deserialize<'de>,
+{
+    type Item = Result<T, JaclDeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let next = self.de.skip_non_tokens().and_then(|_| {
+            if self.de.input.is_empty() {
+                Ok(None)
+            } else {
+                T::deserialize(&mut self.de).map(Some)
+            }
+        });
+        match next {
+            Ok(next) => next.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+// JACL isn't self-delimiting, so the whole stream is read before parsing.
+pub fn from_reader<R, T>(mut reader: R) -> Result<T, JaclDeError>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    from_slice(&bytes)
+}
+
+pub fn from_slice<T>(bytes: &[u8]) -> Result<T, JaclDeError>
+where
+    T: DeserializeOwned,
+{
+    match std::str::from_utf8(bytes) {
+        Ok(s) => from_str(s),
+        Err(e) => Err(JaclDeError::without_span(ErrorKind::InvalidUtf8(
+            e.valid_up_to(),
+        ))),
+    }
+}
+
+/// Reads and deserializes the file at `path`. Unlike the other entry points,
+/// this resolves `@include "other.jacl"` directives, each of which is
+/// replaced by the contents of the named file. Paths are relative to the
+/// directory of the file the directive is in, and a file can't include
+/// itself, directly or through other files.
+///
+/// Errors point at the line and column in the file they're in, which
+/// [`JaclDeError::file`] names, rather than in the text with includes
+/// spliced in.
+pub fn from_path<T>(path: impl AsRef<Path>) -> Result<T, JaclDeError>
+where
+    T: DeserializeOwned,
+{
+    from_path_with_options(path, &DeOptions::new())
+}
+
+/// Like [`from_path`], but parsed according to `options`, which apply to the
+/// included files too.
+pub fn from_path_with_options<T>(
+    path: impl AsRef<Path>,
+    options: &DeOptions,
+) -> Result<T, JaclDeError>
+where
+    T: DeserializeOwned,
+{
+    let path = path.as_ref();
+    let mut stack = vec![fs::canonicalize(path)?];
+    let s = fs::read_to_string(path)?;
+    let mut sources = Sources::default();
+    expand_includes(&s, path, &mut stack, &mut sources)?;
+    from_str_with_options(sources.text.as_str(), options).map_err(|err| sources.locate(err))
+}
+
+// The text read by `from_path`, with includes spliced in, and where each part
+// of it came from.
+#[derive(Default)]
+struct Sources {
+    text: String,
+    // the path and contents of each file read
+    files: Vec<(PathBuf, String)>,
+    // `(start, file, offset)` for each run of `text` copied from one file:
+    // where it starts in `text`, the index of the file and where it starts
+    // in the file
+    runs: Vec<(usize, usize, usize)>,
+}
+
+impl Sources {
+    // moves an error in `text` to the file and line it came from
+    fn locate(&self, err: JaclDeError) -> JaclDeError {
+        if err.line == 0 || err.file.is_some() {
+            return err;
+        }
+        let line_start = line_starts(&self.text)[err.line - 1];
+        let offset = line_start
+            + self.text[line_start..]
+                .chars()
+                .take(err.col)
+                .map(char::len_utf8)
+                .sum::<usize>();
+        let run = self.runs.partition_point(|&(start, _, _)| start <= offset) - 1;
+        let (start, file, file_offset) = self.runs[run];
+        let (path, contents) = &self.files[file];
+        let offset = file_offset + offset - start;
+        let mut located = JaclDeError::located(contents, &line_starts(contents), offset, err.kind);
+        located.hint = err.hint;
+        located.file = Some(path.clone());
+        located
+    }
+}
+
+// splices the files named by the `@include` directives in `s`, the contents
+// of `path`, into `sources`, outside of strings and comments. `stack` holds
+// the files being expanded.
+fn expand_includes(
+    s: &str,
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    sources: &mut Sources,
+) -> Result<(), JaclDeError> {
+    let index = sources.files.len();
+    sources.files.push((path.to_path_buf(), s.to_string()));
+    sources.runs.push((sources.text.len(), index, 0));
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    // errors found here are located in this file
+    let in_file = |mut err: JaclDeError| {
+        err.file.get_or_insert_with(|| path.to_path_buf());
+        err
+    };
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        let skipped = match c {
+            '"' => parsing::string::string(rest).map(|(rest, _)| rest).ok(),
+            '/' | '#' => alt((
+                parsing::comment::multiline_comment::<(&str, nom::error::ErrorKind)>,
+                parsing::comment::eol_comment,
+                parsing::comment::hash_comment,
+            ))(rest)
+            .map(|(rest, _)| rest)
+            .ok(),
+            // `@includes` isn't a directive
+            '@' if rest.starts_with("@include")
+                && !rest["@include".len()..].starts_with(parsing::is_identifier_char) =>
+            {
+                let site = s.len() - rest.len();
+                rest = &rest["@include".len()..];
+                rest = parsing::blank(rest).map_or(rest, |(rest, _)| rest);
+                let offset = s.len() - rest.len();
+                let file = match parsing::string::string(rest) {
+                    Ok((after, file)) => {
+                        rest = after;
+                        file
+                    }
+                    Err(_) => {
+                        let err = JaclDeError::expected_at(s, offset, "a quoted path");
+                        return Err(in_file(err));
+                    }
+                };
+                let included = dir.join(&*file);
+                let cant_include = |err| {
+                    let hint = format!("can't include `{}`: {}", file, err);
+                    in_file(JaclDeError::hinted_at(s, site, hint))
+                };
+                let canonical = fs::canonicalize(&included).map_err(cant_include)?;
+                if stack.contains(&canonical) {
+                    let hint = format!("`{}` includes itself", file);
+                    return Err(in_file(JaclDeError::hinted_at(s, site, hint)));
+                }
+                let contents = fs::read_to_string(&included).map_err(cant_include)?;
+                stack.push(canonical);
+                expand_includes(&contents, &included, stack, sources)?;
+                stack.pop();
+                // ends a `//` comment on the last line of the included file,
+                // which would otherwise run on into this one
+                sources.text.push('\n');
+                // the rest of this file continues from after the directive
+                let resume = s.len() - rest.len();
+                sources.runs.push((sources.text.len(), index, resume));
+                continue;
+            }
+            _ => None,
+        };
+        let next = skipped.unwrap_or(&rest[c.len_utf8()..]);
+        sources.text.push_str(&rest[..(rest.len() - next.len())]);
+        rest = next;
+    }
+    Ok(())
+}
+
+pub(crate) fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let pairs = s.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+    pairs
+        .map(|pair| {
+            if !pair.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()
+        })
+        .collect()
 }
 
 impl<'de> Deserializer<'de> {
     fn skip_non_tokens(&mut self) -> Result<(), JaclDeError> {
+        // an unconsumed implicit delimiter means a container was expected
+        // here, e.g. `abc` being deserialized into a number
         if self.pre.is_some() {
-            unreachable!("There's a bug in the parser! This should never happen! If pre hasn't been consumed, we shouldn't be skipping tokens... pre = {:?}", self.pre);
+            return Err(JaclDeError::new(self));
         }
+        let strict_commas = self.strict_commas;
         self.input = many0(alt((
             parsing::comment::multiline_comment,
             parsing::comment::eol_comment,
-            parsing::whitespace,
+            parsing::comment::hash_comment,
+            |input| {
+                if strict_commas {
+                    parsing::blank(input)
+                } else {
+                    parsing::whitespace(input)
+                }
+            },
         )))(self.input)
         .unwrap_or((self.input, vec![]))
         .0;
-        return Ok(());
+        Ok(())
     }
 
     fn parse_null(&mut self) -> Result<(), JaclDeError> {
@@ -185,10 +1207,10 @@ impl<'de> Deserializer<'de> {
                 self.input = inp;
                 Ok(b)
             }
-            Err(_) => Err(JaclDeError::new(self)),
+            Err(_) => Err(JaclDeError::expected(self, "`null`")),
         };
         self.skip_non_tokens()?;
-        return v;
+        v
     }
 
     fn parse_bool(&mut self) -> Result<bool, JaclDeError> {
@@ -198,10 +1220,10 @@ impl<'de> Deserializer<'de> {
                 self.input = inp;
                 Ok(b)
             }
-            Err(_) => Err(JaclDeError::new(self)),
+            Err(_) => Err(JaclDeError::expected(self, "a boolean")),
         };
         self.skip_non_tokens()?;
-        return v;
+        v
     }
 
     fn parse_int<T: Integer + FromStr>(&mut self) -> Result<T, JaclDeError> {
@@ -211,10 +1233,32 @@ impl<'de> Deserializer<'de> {
                 self.input = inp;
                 Ok(i)
             }
-            Err(_) => Err(JaclDeError::new(self)),
+            Err(_) => Err(self.int_error::<T>()),
         };
         self.skip_non_tokens()?;
-        return v;
+        v
+    }
+
+    // tells an integer that doesn't fit in `T` apart from something that
+    // isn't an integer at all
+    fn int_error<T>(&self) -> JaclDeError {
+        let rest = match parsing::literal::integer::<i128>(self.input) {
+            Ok((rest, _)) => rest,
+            Err(_) => match parsing::literal::integer::<u128>(self.input) {
+                Ok((rest, _)) => rest,
+                Err(_) => match parsing::literal::decimal_digits(self.input) {
+                    Ok((rest, _)) => rest,
+                    Err(_) => return JaclDeError::expected(self, "an integer"),
+                },
+            },
+        };
+        let value = &self.input[..(self.input.len() - rest.len())];
+        let hint = format!(
+            "value {} out of range for {}",
+            value,
+            std::any::type_name::<T>()
+        );
+        JaclDeError::hinted(self, hint)
     }
 
     fn parse_float<T: Float + FromStr>(&mut self) -> Result<T, JaclDeError> {
@@ -224,64 +1268,403 @@ impl<'de> Deserializer<'de> {
                 self.input = inp;
                 Ok(f)
             }
-            Err(_) => Err(JaclDeError::new(self)),
+            Err(_) => Err(JaclDeError::expected(self, "a float")),
         };
         self.skip_non_tokens()?;
-        return v;
+        v
     }
 
-    fn parse_string(&mut self) -> Result<String, JaclDeError> {
+    fn parse_string(&mut self) -> Result<Cow<'de, str>, JaclDeError> {
         self.skip_non_tokens()?;
+        // errors point at the token that isn't a string, the opening quote
+        // of an unterminated one, or the offending escape sequence
+        let start = self.input;
         let v = match parsing::string::string(self.input) {
-            Ok((inp, st)) => match st {
-                Ok(s) => {
-                    self.input = inp;
-                    Ok(s)
+            Ok((inp, s)) if self.env_interpolation => {
+                match parsing::string::interpolate(s, |name| std::env::var(name).ok()) {
+                    Ok(s) => {
+                        self.input = inp;
+                        Ok(s)
+                    }
+                    Err(err) => {
+                        let hint = match err {
+                            InterpolateError::Unterminated => {
+                                "expected `}` to close `${`".to_string()
+                            }
+                            InterpolateError::Undefined(name) => {
+                                format!("environment variable `{}` isn't set", name)
+                            }
+                        };
+                        self.input = start;
+                        Err(JaclDeError::hinted(self, hint))
+                    }
                 }
-                Err(_) => Err(JaclDeError::new(self)),
-            },
-            Err(_) => Err(JaclDeError::new(self)),
+            }
+            Ok((inp, s)) => {
+                self.input = inp;
+                Ok(s)
+            }
+            Err(StringError::InvalidEscape(offset))
+            | Err(StringError::InvalidCodePoint(offset)) => {
+                self.input = &self.input[offset..];
+                Err(JaclDeError::new(self))
+            }
+            Err(_) => Err(JaclDeError::expected(self, "a string")),
         };
         self.skip_non_tokens()?;
-        return v;
+        v
+    }
+
+    // Bytes are either a sequence of integers in 0..=255, e.g. [222 173 190 239],
+    // or a string of hex digit pairs, e.g. "deadbeef".
+    fn parse_bytes(&mut self) -> Result<Vec<u8>, JaclDeError> {
+        if self.pre.is_none() {
+            self.skip_non_tokens()?;
+        }
+        if self.next_char()? == '"' {
+            let start = self.input;
+            let s = self.parse_string()?;
+            return match decode_hex(&s) {
+                Some(bytes) => Ok(bytes),
+                None => {
+                    self.input = start;
+                    Err(JaclDeError::new(self))
+                }
+            };
+        }
+        self.parse_seq(None, ByteSeq)
     }
 
     fn parse_delim(&mut self) -> Result<char, JaclDeError> {
         if let Some(c) = self.pre {
             self.pre = None;
+            self.last_open = None;
             return Ok(c);
         }
         self.skip_non_tokens()?;
-        if self.input.len() == 0 {
-            if let Some(c) = self.post {
-                self.post = None;
-                return Ok(c);
-            } else {
-                return Err(JaclDeError::new(self));
+        if self.input.is_empty() {
+            match self.post {
+                Some(c) if !self.in_explicit_container() => {
+                    self.post = None;
+                    return Ok(c);
+                }
+                _ => return Err(self.end_of_input()),
             }
         }
         let v = match parsing::delimiter(self.input) {
             Ok((inp, c)) => {
+                if matches!(c, '(' | '{' | '[') {
+                    self.last_open =
+                        Some(self.input.as_ptr() as usize - self.begin.as_ptr() as usize);
+                }
                 self.input = inp;
                 Ok(c)
             }
             Err(_) => Err(JaclDeError::new(self)),
         };
         self.skip_non_tokens()?;
-        return v;
+        v
+    }
+
+    // parses the delimiter `expected`, or errors pointing at whatever is there instead
+    fn expect_delim(&mut self, expected: char) -> Result<(), JaclDeError> {
+        if self.pre.is_none() {
+            self.skip_non_tokens()?;
+        }
+        let start = self.input;
+        match self.parse_delim() {
+            Ok(c) if c == expected => Ok(()),
+            _ => {
+                self.input = start;
+                Err(JaclDeError::expected(self, &format!("`{}`", expected)))
+            }
+        }
+    }
+
+    // like `expect_delim`, but when a different kind of container was written
+    // the hint names both kinds, e.g. a struct written with map braces
+    fn expect_open(&mut self, open: char) -> Result<(), JaclDeError> {
+        if self.pre.is_none() {
+            self.skip_non_tokens()?;
+            let found = self.input.chars().next().filter(|&c| c != open);
+            if let (Some(found), Some(expected)) = (found, container_kind(open)) {
+                if let Some(kind) = container_kind(found) {
+                    let close = match open {
+                        '(' => ')',
+                        '{' => '}',
+                        _ => ']',
+                    };
+                    let hint = format!(
+                        "expected {} `{}...{}`, found {} `{}`",
+                        expected, open, close, kind, found
+                    );
+                    return Err(JaclDeError::hinted(self, hint));
+                }
+            }
+        }
+        self.expect_delim(open)
+    }
+
+    // the `:` between a key and its value, or `=` if enabled
+    fn parse_key_separator(&mut self) -> Result<(), JaclDeError> {
+        if !self.equals_separator {
+            return self.expect_delim(':');
+        }
+        self.skip_non_tokens()?;
+        if self.input.starts_with('=') {
+            self.input = &self.input[1..];
+            return self.skip_non_tokens();
+        }
+        let start = self.input;
+        match self.parse_delim() {
+            Ok(':') => Ok(()),
+            _ => {
+                self.input = start;
+                Err(JaclDeError::expected(self, "`:` or `=`"))
+            }
+        }
+    }
+
+    // Deserializes a value in a container, resolving anchors and references
+    // when they're enabled.
+    fn deserialize_value<T>(&mut self, seed: T) -> Result<T::Value, JaclDeError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.skip_non_tokens()?;
+        let start = self.input;
+        // errors from `Deserialize` impls, like an unparseable timestamp in a
+        // `deserialize_with` function, have no location of their own
+        self.deserialize_anchored(seed)
+            .map_err(|err| match err.line {
+                0 => {
+                    let offset = start.as_ptr() as usize - self.begin.as_ptr() as usize;
+                    JaclDeError::located(self.begin, &self.line_starts(), offset, err.kind)
+                }
+                _ => err,
+            })
+    }
+
+    fn deserialize_anchored<T>(&mut self, seed: T) -> Result<T::Value, JaclDeError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.anchors.is_none() {
+            return seed.deserialize(self);
+        }
+        let start = self.input;
+        if self.input.starts_with('&') {
+            self.input = &self.input[1..];
+            let name = self.parse_anchor_name()?;
+            // read the value once to find where it ends, then for real
+            let value_start = self.input;
+            self.skip_value()?;
+            let source = &value_start[..(value_start.len() - self.input.len())];
+            self.input = value_start;
+            let value = seed.deserialize(&mut *self)?;
+            // defined afterwards, so the value can't refer to itself
+            self.anchors.as_mut().unwrap().push((name, source));
+            return Ok(value);
+        }
+        if self.input.starts_with('*') {
+            self.input = &self.input[1..];
+            let name = self.parse_anchor_name()?;
+            // the latest definition of a name wins
+            let anchors = self.anchors.as_ref().unwrap();
+            let source = match anchors.iter().rev().find(|(def, _)| *def == name) {
+                Some((_, source)) => *source,
+                None => {
+                    self.input = start;
+                    return Err(JaclDeError::hinted(
+                        self,
+                        format!("undefined anchor `{}`", name),
+                    ));
+                }
+            };
+            let limit = (self.begin.len() * EXPANSION_FACTOR).max(MIN_EXPANSION);
+            self.expanded += source.len();
+            if self.expanded > limit {
+                self.input = start;
+                return Err(JaclDeError::spanned(self, ErrorKind::ExpansionLimit(limit)));
+            }
+            let rest = self.input;
+            let defined = anchors.len();
+            // errors in the copy point at the anchored value
+            self.input = source;
+            let value = seed.deserialize(&mut *self);
+            // anchors in the copy were defined when it was first read
+            self.anchors.as_mut().unwrap().truncate(defined);
+            let value = value?;
+            self.input = rest;
+            return Ok(value);
+        }
+        seed.deserialize(self)
+    }
+
+    fn parse_anchor_name(&mut self) -> Result<&'de str, JaclDeError> {
+        match parsing::identifier(self.input) {
+            Ok((rest, name)) => {
+                self.input = rest;
+                self.skip_non_tokens()?;
+                Ok(name)
+            }
+            Err(_) => Err(JaclDeError::expected(self, "an anchor name")),
+        }
+    }
+
+    // Skips one value without building it, checking it as strictly as it
+    // would be read. Containers are tracked on a stack of their own rather
+    // than by recursing, so ignored values can be nested arbitrarily deeply.
+    fn skip_value(&mut self) -> Result<(), JaclDeError> {
+        // each open container, with its closer and whether it's still empty
+        let mut open: Vec<(DataType, char, bool)> = Vec::new();
+        let mut next = Skip::Item;
+        loop {
+            self.skip_non_tokens()?;
+            match next {
+                Skip::Separator => {
+                    self.parse_key_separator()?;
+                    next = Skip::Value;
+                    continue;
+                }
+                Skip::Item if !open.is_empty() => {
+                    let (datatype, close, first) = *open.last().unwrap();
+                    let mut separated = Separated::new(self, datatype);
+                    separated.first = first;
+                    separated.separator()?;
+                    open.last_mut().unwrap().2 = separated.first;
+                    if self.input.starts_with(close) {
+                        self.input = &self.input[1..];
+                        open.pop();
+                        if open.is_empty() {
+                            return self.skip_non_tokens();
+                        }
+                        continue;
+                    }
+                    // field names are strings or identifiers, and map keys are
+                    // strings or other literals
+                    if datatype != DataType::Seq {
+                        let rest = match self.input.chars().next() {
+                            Some('"') => parsing::string::string(self.input).ok().map(|r| r.0),
+                            Some(_) if datatype == DataType::Struct => {
+                                parsing::identifier(self.input).ok().map(|r| r.0)
+                            }
+                            Some(c) if !parsing::is_delimiter(c) => scalar_end(self.input),
+                            _ => None,
+                        };
+                        let rest = match rest {
+                            Some(rest) => rest,
+                            None if datatype == DataType::Struct => {
+                                return Err(JaclDeError::expected(self, "a field name or `)`"));
+                            }
+                            None => return Err(JaclDeError::expected(self, "a key or `}`")),
+                        };
+                        self.input = rest;
+                        next = Skip::Separator;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            // a value, either on its own, an element or the value of an entry
+            let rest = match self.input.chars().next() {
+                Some(c @ '[') | Some(c @ '{') | Some(c @ '(') => {
+                    self.input = &self.input[1..];
+                    let (datatype, close) = match c {
+                        '[' => (DataType::Seq, ']'),
+                        '{' => (DataType::HashMap, '}'),
+                        _ => (DataType::Struct, ')'),
+                    };
+                    open.push((datatype, close, true));
+                    next = Skip::Item;
+                    continue;
+                }
+                Some(c @ '&') | Some(c @ '*') if self.anchors.is_some() => {
+                    self.input = &self.input[1..];
+                    self.parse_anchor_name()?;
+                    // an anchor is followed by the value it names
+                    if c == '&' {
+                        next = Skip::Value;
+                        continue;
+                    }
+                    Some(self.input)
+                }
+                Some(c) if !parsing::is_delimiter(c) => scalar_end(self.input),
+                _ => None,
+            };
+            match (rest, next, open.last()) {
+                (Some(rest), _, _) => self.input = rest,
+                // a closer that doesn't match, or a stray `:`
+                (None, Skip::Item, Some((_, close, _)))
+                    if self.input.starts_with(parsing::is_delimiter) =>
+                {
+                    return Err(JaclDeError::expected(self, &format!("`{}`", close)));
+                }
+                (None, _, _) => return Err(JaclDeError::expected(self, "a value")),
+            }
+            if open.is_empty() {
+                return self.skip_non_tokens();
+            }
+            next = Skip::Item;
+        }
+    }
+
+    // `len` is the number of elements a tuple or array needs
+    fn parse_seq<V>(&mut self, len: Option<usize>, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.expect_delim('[')?;
+        self.enter()?;
+        let mut seq = Separated::new(self, DataType::Seq);
+        seq.len = len;
+        let value = visitor.visit_seq(&mut seq);
+        let ended = seq.ended;
+        self.leave();
+        let value = value?;
+        // fixed length visitors, like tuples, stop without reading the `]`
+        if !ended {
+            if let Some(len) = len {
+                self.skip_non_tokens()?;
+                if self.next_char()? != ']' {
+                    let hint = format!("expected {} elements, found more", len);
+                    return Err(JaclDeError::hinted(self, hint));
+                }
+            }
+            self.expect_delim(']')?;
+        }
+        Ok(value)
+    }
+
+    // `fields` is None when any field is allowed, e.g. for a `Value`
+    fn parse_struct<V>(
+        &mut self,
+        fields: Option<&'static [&'static str]>,
+        visitor: V,
+    ) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.expect_open('(')?;
+        self.enter()?;
+        let mut access = Separated::new(self, DataType::Struct);
+        access.fields = fields;
+        let value = visitor.visit_map(access);
+        self.leave();
+        value
     }
 
-    fn parse_identifier(&mut self) -> Result<&str, JaclDeError> {
+    fn parse_identifier(&mut self) -> Result<&'de str, JaclDeError> {
         self.skip_non_tokens()?;
         let v = match parsing::identifier(self.input) {
             Ok((inp, s)) => {
                 self.input = inp;
                 Ok(s)
             }
-            Err(_) => Err(JaclDeError::new(self)),
+            Err(_) => Err(JaclDeError::expected(self, "an identifier")),
         };
         self.skip_non_tokens()?;
-        return v;
+        v
     }
 
     fn next_char(&self) -> Result<char, JaclDeError> {
@@ -291,14 +1674,14 @@ impl<'de> Deserializer<'de> {
         if let Some(next) = self.input.chars().next() {
             return Ok(next);
         }
-        if let Some(post) = self.post {
-            return Ok(post);
+        match self.post {
+            Some(post) if !self.in_explicit_container() => Ok(post),
+            _ => Err(self.end_of_input()),
         }
-        return Err(JaclDeError::new(self));
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = JaclDeError;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
@@ -309,20 +1692,36 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
             self.skip_non_tokens().unwrap_or(());
         }
         match self.next_char()? {
-            'n' => self.deserialize_option(visitor),
-            't' | 'f' => self.deserialize_bool(visitor),
+            'n' | 'N' | 'i' | 'I' if parsing::literal::float::<f64>(self.input).is_ok() => {
+                self.deserialize_f64(visitor)
+            }
+            'n' if parsing::literal::null(self.input).is_ok() => self.deserialize_option(visitor),
+            't' | 'f' if parsing::literal::boolean(self.input).is_ok() => {
+                self.deserialize_bool(visitor)
+            }
             '"' => self.deserialize_str(visitor),
-            '-' | '0'..='9' => match parsing::literal::integer::<i64>(self.input) {
-                Ok(res) => match res.0.chars().next() {
-                    Some('.') => self.deserialize_f64(visitor),
-                    _ => self.deserialize_i64(visitor),
+            // a fraction or exponent after the digits makes it a float, e.g. `1.5` or `1e10`
+            '-' | '+' | '0'..='9' => match parsing::literal::integer::<i64>(self.input) {
+                Ok((rest, _)) if !parsing::literal::is_float_suffix(rest) => {
+                    self.deserialize_i64(visitor)
+                }
+                Ok(_) => self.deserialize_f64(visitor),
+                // too large for an i64, so try the unsigned range before giving up on integers
+                Err(_) => match parsing::literal::integer::<u64>(self.input) {
+                    Ok((rest, _)) if !parsing::literal::is_float_suffix(rest) => {
+                        self.deserialize_u64(visitor)
+                    }
+                    _ => self.deserialize_f64(visitor),
                 },
-                Err(_) => Err(JaclDeError::new(self)),
             },
             '[' => self.deserialize_seq(visitor),
             '{' => self.deserialize_map(visitor),
-            '(' => self.deserialize_struct("", &[""], visitor),
-            _ => Err(JaclDeError::new(self)),
+            '(' => self.parse_struct(None, visitor),
+            // a bare word like `red` in `color : red` is read as a string
+            _ if parsing::identifier(self.input).is_ok() => {
+                visitor.visit_borrowed_str(self.parse_identifier()?)
+            }
+            _ => Err(JaclDeError::expected(self, "a value")),
         }
     }
 
@@ -389,6 +1788,20 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_u64(self.parse_int()?)
     }
 
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i128(self.parse_int()?)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u128(self.parse_int()?)
+    }
+
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
@@ -403,63 +1816,91 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_f64(self.parse_float()?)
     }
 
-    fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value, JaclDeError>
+    // Chars are written as a string literal containing exactly one unicode
+    // scalar value, e.g. "a" or "λ".
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        self.skip_non_tokens()?;
+        let start = self.input;
+        let s = self.parse_string()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => {
+                self.input = start;
+                Err(JaclDeError::new(self))
+            }
+        }
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        let s = self.parse_string()?;
-        visitor.visit_str(&s)
+        match self.parse_string()? {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_string(self.parse_string()?)
+        visitor.visit_string(self.parse_string()?.into_owned())
     }
 
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value, JaclDeError>
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let bytes = self.parse_bytes()?;
+        visitor.visit_bytes(&bytes)
     }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value, JaclDeError>
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        visitor.visit_byte_buf(self.parse_bytes()?)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        self.skip_non_tokens()?;
-        if self.input.starts_with("null") {
-            self.input = &self.input["null".len()..];
-            visitor.visit_none()
-        } else {
-            visitor.visit_some(self)
+        // an implicit wrapper means a container, so it can't be null
+        if self.pre.is_none() {
+            self.skip_non_tokens()?;
+            // `null` must be the whole word, so `nullable` isn't None
+            if parsing::literal::null(self.input).is_ok() {
+                self.parse_null()?;
+                return visitor.visit_none();
+            }
         }
+        visitor.visit_some(self)
     }
 
+    // A unit is written as either `null` or an empty struct `()`.
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        Err(JaclDeError::new(self))
-        
+        if self.pre.is_none() {
+            self.skip_non_tokens()?;
+        }
+        if self.next_char()? == '(' {
+            self.parse_delim()?;
+            self.expect_delim(')')?;
+        } else {
+            self.parse_null()?;
+        }
+        visitor.visit_unit()
     }
 
+    // Unit structs look just like units.
     fn deserialize_unit_struct<V>(
         self,
         _name: &'static str,
@@ -468,29 +1909,54 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        Err(JaclDeError::new(self))
+        self.deserialize_unit(visitor)
     }
 
+    // As is done here, deserializers are encouraged to treat newtype structs as
+    // insignificant wrappers around the data they contain.
+    #[cfg_attr(not(feature = "bigint"), allow(unused_variables))]
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        Err(JaclDeError::new(self))
+        #[cfg(feature = "bigint")]
+        if name == crate::bigint::BIGINT_NAME {
+            let int: num_bigint::BigInt = self.parse_int()?;
+            return visitor.visit_string(int.to_string());
+        }
+        visitor.visit_newtype_struct(self)
     }
 
-    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value, JaclDeError>
+    // A map can also be read as a sequence of `(key, value)` pairs, so that
+    // types like `Vec<(String, T)>` keep its entries in document order. So can
+    // a struct, with bare keys, including the top level of a document of
+    // `key : value` entries. The elements have to be pairs; for any other type
+    // the map is an error, as if it had been read as an ordinary sequence.
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        if self.parse_delim()? == '[' {
-            return visitor.visit_seq(Separated::new(&mut self, DataType::SEQ));
-        } else {
-            Err(JaclDeError::new(self))
+        if self.pre.is_none() {
+            self.skip_non_tokens()?;
         }
+        let (delim, datatype) = match self.next_char()? {
+            '{' => ('{', DataType::HashMap),
+            '(' => ('(', DataType::Struct),
+            _ => return self.parse_seq(None, visitor),
+        };
+        let open = self.input;
+        self.expect_delim(delim)?;
+        self.enter()?;
+        let value = visitor.visit_seq(Entries {
+            map: Separated::new(self, datatype),
+            open,
+        });
+        self.leave();
+        value
     }
 
     // Tuples look just like sequences in JSON. Some formats may be able to
@@ -499,38 +1965,83 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     // As indicated by the length parameter, the `Deserialize` implementation
     // for a tuple in the Serde data model is required to know the length of the
     // tuple before even looking at the input data.
-    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, JaclDeError>
+    //
+    // Byte arrays like `[u8; 32]` can also be written as a string of exactly
+    // `2 * len` hex digits, as `Vec<u8>` can with `serde_bytes`. Whether the
+    // elements are bytes is only known once the first one is read, so other
+    // tuples fail there, as they would on any string.
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        if self.pre.is_none() {
+            self.skip_non_tokens()?;
+            if self.input.starts_with('"') {
+                let start = self.input;
+                let s = self.parse_string()?;
+                let decoded = decode_hex(&s);
+                let found = decoded.as_ref().map(Vec::len);
+                let not_bytes = Cell::new(false);
+                let bytes = HexBytes {
+                    bytes: decoded
+                        .filter(|bytes| bytes.len() == len)
+                        .map(Vec::into_iter),
+                    not_bytes: &not_bytes,
+                };
+                let err = match visitor.visit_seq(bytes) {
+                    Ok(value) => return Ok(value),
+                    Err(err) => err,
+                };
+                self.input = start;
+                if not_bytes.get() {
+                    return Err(JaclDeError::expected(self, "`[`"));
+                }
+                let hint = match found {
+                    Some(found) if found != len => {
+                        format!("expected {} bytes, found {}", len, found)
+                    }
+                    Some(_) => return Err(err),
+                    None => "expected a string of hex digit pairs".to_string(),
+                };
+                return Err(JaclDeError::hinted(self, hint));
+            }
+        }
+        self.parse_seq(Some(len), visitor)
     }
 
     // Tuple structs look just like sequences in JSON.
     fn deserialize_tuple_struct<V>(
         self,
         _name: &'static str,
-        _len: usize,
+        len: usize,
         visitor: V,
     ) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        self.parse_seq(Some(len), visitor)
     }
 
     // Much like `deserialize_seq` but calls the visitors `visit_map` method
     // with a `MapAccess` implementation, rather than the visitor's `visit_seq`
     // method with a `SeqAccess` implementation.
-    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value, JaclDeError>
+    // A struct can also be read as a map. Structs with a `#[serde(flatten)]`
+    // field are deserialized as maps, since they take keys they don't name.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        if self.parse_delim()? == '{' {
-            return visitor.visit_map(Separated::new(&mut self, DataType::HASHMAP));
-        } else {
-            Err(JaclDeError::new(self))
+        if self.pre.is_none() {
+            self.skip_non_tokens()?;
         }
+        if self.next_char()? == '(' {
+            return self.parse_struct(None, visitor);
+        }
+        self.expect_open('{')?;
+        self.enter()?;
+        let value = visitor.visit_map(Separated::new(self, DataType::HashMap));
+        self.leave();
+        value
     }
 
     // Structs look just like maps in JSON.
@@ -540,42 +2051,76 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     // are before even looking at the input data. Any key-value pairing in which
     // the fields cannot be known ahead of time is probably a map.
     fn deserialize_struct<V>(
-        mut self,
+        self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        if self.parse_delim()? == '(' {
-            return visitor.visit_map(Separated::new(&mut self, DataType::STRUCT));
-        } else {
-            Err(JaclDeError::new(self))
-        }
+        self.parse_struct(Some(fields), visitor)
     }
 
+    // Unit variants are a bare identifier, `Red`, or a quoted string, `"Red"`,
+    // which is the form the serializer writes. Other variants are the
+    // identifier followed by their contents, `Circle : 1.5`, `Pair : [1 2]` and
+    // `Point : (x : 1 y : 2)`. At the top level the implicit struct wrapping
+    // turns these into `(Red)` and `(Circle : 1.5)`, so parentheses around
+    // the whole enum are accepted too.
     fn deserialize_enum<V>(
         self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
-        _visitor: V,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
     ) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        unimplemented!();
+        if self.pre.is_none() {
+            self.skip_non_tokens()?;
+        }
+        if cfg!(any(unix, windows))
+            && name == "OsString"
+            && variants == ["Unix", "Windows"]
+            && self.next_char()? == '"'
+        {
+            let s = self.parse_string()?;
+            return visitor.visit_enum(PlatformString(s.into_owned()));
+        }
+        // a quoted string names a unit variant, as in `level : "debug"`
+        if self.next_char()? == '"' {
+            let variant = self.parse_string()?;
+            return visitor.visit_enum(CowStrDeserializer::<JaclDeError>::new(variant));
+        }
+        if self.next_char()? == '(' {
+            self.parse_delim()?;
+            self.enter()?;
+            let value = visitor.visit_enum(Enum::new(self))?;
+            self.leave();
+            self.expect_delim(')')?;
+            Ok(value)
+        } else {
+            visitor.visit_enum(Enum::new(self))
+        }
     }
 
     // An identifier in Serde is the type that identifies a field of a struct or
     // the variant of an enum. In JSON, struct fields and enum variants are
     // represented as strings. In other formats they may be represented as
     // numeric indices.
+    //
+    // Identifiers may also be quoted, as they are for map keys that a tagged
+    // enum reads through a map.
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_str(self.parse_identifier()?)
+        self.skip_non_tokens()?;
+        if self.next_char()? == '"' {
+            return self.deserialize_str(visitor);
+        }
+        visitor.visit_borrowed_str(self.parse_identifier()?)
     }
 
     // Like `deserialize_any` but indicates to the `Deserializer` that it makes
@@ -589,117 +2134,619 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     // Some formats are not able to implement this at all. Formats that can
     // implement `deserialize_any` and `deserialize_ignored_any` are known as
     // self-describing.
+    //
+    // Ignored values are skipped over token by token rather than built, so
+    // ignoring a large subtree is cheap.
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        if self.pre.is_some() {
+            return self.deserialize_any(visitor);
+        }
+        self.skip_value()?;
+        visitor.visit_unit()
     }
 }
 
-
-// In order to handle commas correctly when deserializing a JSON array or map,
-// we need to track whether we are on the first element or past the first
-// element.
-struct Separated<'a, 'de: 'a> {
+struct Enum<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
-    datatype: DataType,
 }
 
-impl<'a, 'de> Separated<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>, datatype: DataType) -> Self {
-        Separated { de, datatype }
+impl<'a, 'de> Enum<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Self {
+        Enum { de }
+    }
+
+    // variants with contents separate them from the name with a colon
+    fn parse_colon(&mut self) -> Result<(), JaclDeError> {
+        self.de.expect_delim(':')
     }
 }
 
-// `SeqAccess` is provided to the `Visitor` to give it the ability to iterate
-// through elements of the sequence.
-impl<'de, 'a> SeqAccess<'de> for Separated<'a, 'de> {
+impl<'de, 'a> EnumAccess<'de> for Enum<'a, 'de> {
     type Error = JaclDeError;
+    type Variant = Self;
 
-    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, JaclDeError>
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), JaclDeError>
     where
-        T: DeserializeSeed<'de>,
+        V: DeserializeSeed<'de>,
     {
-        if self.de.next_char()? == ']' {
-            self.de.parse_delim()?;
-            Ok(None)
-        } else {
-            seed.deserialize(&mut *self.de).map(Some)
-        }
+        let variant = self.de.parse_identifier()?;
+        let value = seed.deserialize(BorrowedStrDeserializer::<JaclDeError>::new(variant))?;
+        Ok((value, self))
     }
 }
 
-// `MapAccess` is provided to the `Visitor` to give it the ability to iterate
-// through entries of the map.
-impl<'de, 'a> MapAccess<'de> for Separated<'a, 'de> {
+impl<'de, 'a> VariantAccess<'de> for Enum<'a, 'de> {
     type Error = JaclDeError;
 
-    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, JaclDeError>
+    fn unit_variant(self) -> Result<(), JaclDeError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(mut self, seed: T) -> Result<T::Value, JaclDeError>
     where
-        K: DeserializeSeed<'de>,
+        T: DeserializeSeed<'de>,
     {
-        if let Ok(val) = self.de.parse_delim() {
-            if (val == '}' && self.datatype == DataType::HASHMAP)
-                || (val == ')' && self.datatype == DataType::STRUCT)
-            {
-                return Ok(None);
-            } else {
-                return Err(JaclDeError::new(self.de));
-            }
-        }
-        let z = seed.deserialize(&mut *self.de);
-        match z {
-            Ok(z) => Ok(Some(z)),
-            Err(e) => Err(e),
-        }
+        self.parse_colon()?;
+        seed.deserialize(self.de)
     }
 
-    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, JaclDeError>
+    fn tuple_variant<V>(mut self, len: usize, visitor: V) -> Result<V::Value, JaclDeError>
     where
-        V: DeserializeSeed<'de>,
+        V: Visitor<'de>,
     {
-        if let Ok(val) = self.de.parse_delim() {
-            if val == ':' {
-                return seed.deserialize(&mut *self.de);
-            } else {
-                return Err(JaclDeError::new(self.de));
-            }
-        } else {
-            return Err(JaclDeError::new(self.de));
-        }
+        self.parse_colon()?;
+        de::Deserializer::deserialize_tuple(self.de, len, visitor)
     }
 
-    fn size_hint(&self) -> Option<usize> {
-        match self.datatype {
-            DataType::STRUCT => Some(0),
-            DataType::HASHMAP => None,
-            DataType::SEQ => None,
+    fn struct_variant<V>(
+        mut self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.parse_colon()?;
+        de::Deserializer::deserialize_struct(self.de, "", fields, visitor)
+    }
+}
+
+// Reads the `[...]` form of a byte array through `parse_seq`, so it's
+// separated, depth limited and anchored like any other sequence.
+struct ByteSeq;
+
+impl<'de> Visitor<'de> for ByteSeq {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of bytes")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Vec<u8>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
         }
+        Ok(bytes)
     }
 }
 
+// The bytes of a hex string read as a tuple. `bytes` is `None` if the string
+// isn't the right number of bytes, in which case reading the first element
+// fails. Reading anything but a `u8` sets `not_bytes` and fails, so that only
+// tuples of bytes are read from strings.
+struct HexBytes<'a> {
+    bytes: Option<std::vec::IntoIter<u8>>,
+    not_bytes: &'a Cell<bool>,
+}
 
+impl<'de> SeqAccess<'de> for HexBytes<'_> {
+    type Error = JaclDeError;
 
-////////////////////////////////////////////////////////////////////////////////
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, JaclDeError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let byte = match &mut self.bytes {
+            Some(bytes) => match bytes.next() {
+                Some(byte) => Some(byte),
+                None => return Ok(None),
+            },
+            None => None,
+        };
+        seed.deserialize(HexByte {
+            byte,
+            not_bytes: self.not_bytes,
+        })
+        .map(Some)
+    }
+}
 
-mod tests {
-    use super::*;
-    use serde::Deserialize;
-    use std::collections::HashMap;
+struct HexByte<'a> {
+    byte: Option<u8>,
+    not_bytes: &'a Cell<bool>,
+}
 
-    #[test]
-    fn test_struct() {
-        #[derive(Deserialize, PartialEq, Debug)]
-        struct Test {
-            int: u32,
-            vec: Vec<String>,
-            map: HashMap<String, Test>,
-            underscore_test: u8,
+impl<'de> de::Deserializer<'de> for HexByte<'_> {
+    type Error = JaclDeError;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.not_bytes.set(true);
+        Err(de::Error::custom("not a byte"))
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.byte {
+            Some(byte) => visitor.visit_u8(byte),
+            None => Err(de::Error::custom("not hex bytes")),
         }
+    }
 
-        let j = r#"
-            (
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+// serde reads an `OsString` as an enum of the platform's encoding, either
+// `Unix` with the bytes or `Windows` with the UTF-16 code units. A quoted
+// string is read as the variant for the current platform. Other targets have
+// no such variant, so there the string is left to fail as a unit variant.
+struct PlatformString(String);
+
+impl<'de> EnumAccess<'de> for PlatformString {
+    type Error = JaclDeError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), JaclDeError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = if cfg!(windows) { "Windows" } else { "Unix" };
+        let value = seed.deserialize(BorrowedStrDeserializer::<JaclDeError>::new(variant))?;
+        Ok((value, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for PlatformString {
+    type Error = JaclDeError;
+
+    fn unit_variant(self) -> Result<(), JaclDeError> {
+        Err(de::Error::invalid_type(
+            Unexpected::NewtypeVariant,
+            &"unit variant",
+        ))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, JaclDeError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if cfg!(windows) {
+            seed.deserialize(SeqDeserializer::<_, JaclDeError>::new(
+                self.0.encode_utf16(),
+            ))
+        } else {
+            seed.deserialize(SeqDeserializer::<_, JaclDeError>::new(
+                self.0.into_bytes().into_iter(),
+            ))
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            Unexpected::NewtypeVariant,
+            &"tuple variant",
+        ))
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            Unexpected::NewtypeVariant,
+            &"struct variant",
+        ))
+    }
+}
+
+// In order to handle commas correctly when deserializing a JSON array or map,
+// we need to track whether we are on the first element or past the first
+// element.
+struct Separated<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    datatype: DataType,
+    // whether the closing delimiter has been read
+    ended: bool,
+    // whether no element or entry has been read yet
+    first: bool,
+    // the fields of the struct being read, for `deny_unknown_fields`
+    fields: Option<&'static [&'static str]>,
+    // the keys read so far, for `deny_duplicate_keys`
+    seen: HashSet<String>,
+    // the number of elements a tuple or array needs, and how many were read
+    len: Option<usize>,
+    count: usize,
+}
+
+impl<'a, 'de> Separated<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, datatype: DataType) -> Self {
+        Separated {
+            de,
+            datatype,
+            ended: false,
+            first: true,
+            fields: None,
+            seen: HashSet::new(),
+            len: None,
+            count: 0,
+        }
+    }
+
+    // checks a key against the `deny_*` options, with the deserializer
+    // positioned at the start of the key for errors
+    fn check_key(&mut self, key: &str) -> Result<(), JaclDeError> {
+        if self.de.deny_unknown_fields {
+            if let Some(fields) = self.fields {
+                if !fields.contains(&key) {
+                    let expected = fields
+                        .iter()
+                        .map(|field| format!("`{}`", field))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let hint = format!("unknown field `{}`, expected one of {}", key, expected);
+                    return Err(JaclDeError::hinted(self.de, hint));
+                }
+            }
+        }
+        if self.de.deny_duplicate_keys && !self.seen.insert(key.to_string()) {
+            let hint = format!("duplicate key `{}`", key);
+            return Err(JaclDeError::hinted(self.de, hint));
+        }
+        Ok(())
+    }
+
+    fn checks_keys(&self) -> bool {
+        self.de.deny_duplicate_keys || (self.de.deny_unknown_fields && self.fields.is_some())
+    }
+
+    // With strict commas, consumes the comma, if any, before the next element
+    // or entry. Errors point at the comma that shouldn't be there.
+    fn separator(&mut self) -> Result<(), JaclDeError> {
+        if !self.de.strict_commas {
+            return Ok(());
+        }
+        self.de.skip_non_tokens()?;
+        let (item, close) = match self.datatype {
+            DataType::Seq => ("a value", "`]`"),
+            DataType::HashMap => ("a key", "`}`"),
+            DataType::Struct => ("a field name", "`)`"),
+        };
+        if !self.de.input.starts_with(',') {
+            self.first = false;
+            return Ok(());
+        }
+        if self.first {
+            return Err(JaclDeError::expected(
+                self.de,
+                &format!("{} or {}", item, close),
+            ));
+        }
+        let comma = self.de.input;
+        self.de.input = &self.de.input[1..];
+        self.de.skip_non_tokens()?;
+        if self.de.input.starts_with(',') {
+            return Err(JaclDeError::expected(self.de, item));
+        }
+        if matches!(self.de.next_char()?, ']' | '}' | ')') {
+            self.de.input = comma;
+            return Err(JaclDeError::expected(self.de, close));
+        }
+        Ok(())
+    }
+}
+
+// `SeqAccess` is provided to the `Visitor` to give it the ability to iterate
+// through elements of the sequence.
+impl<'de, 'a> SeqAccess<'de> for Separated<'a, 'de> {
+    type Error = JaclDeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, JaclDeError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.separator()?;
+        if self.de.next_char()? == ']' {
+            if let Some(len) = self.len.filter(|&len| self.count < len) {
+                let hint = format!("expected {} elements, found {}", len, self.count);
+                return Err(JaclDeError::hinted(self.de, hint));
+            }
+            self.de.parse_delim()?;
+            self.ended = true;
+            Ok(None)
+        } else {
+            self.count += 1;
+            self.de.deserialize_value(seed).map(Some)
+        }
+    }
+}
+
+// `MapAccess` is provided to the `Visitor` to give it the ability to iterate
+// through entries of the map.
+impl<'de, 'a> MapAccess<'de> for Separated<'a, 'de> {
+    type Error = JaclDeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, JaclDeError>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        self.separator()?;
+        // peek rather than trying parse_delim, so a key doesn't cost an error
+        self.de.skip_non_tokens()?;
+        if parsing::is_delimiter(self.de.next_char()?) {
+            let start = self.de.input;
+            let val = self.de.parse_delim()?;
+            if (val == '}' && self.datatype == DataType::HashMap)
+                || (val == ')' && self.datatype == DataType::Struct)
+            {
+                return Ok(None);
+            } else {
+                self.de.input = start;
+                let expected = match self.datatype {
+                    DataType::Struct => "a field name or `)`",
+                    _ => "a key or `}`",
+                };
+                return Err(JaclDeError::expected(self.de, expected));
+            }
+        }
+        if self.checks_keys() {
+            let start = self.de.input;
+            let key = match parsing::string::string(start) {
+                Ok((_, key)) => key,
+                Err(_) => Cow::Borrowed(key_token(start)),
+            };
+            self.check_key(&key)?;
+            self.de.input = start;
+        }
+        // struct keys are bare identifiers, which would otherwise be read as
+        // values (or keywords like `true`) by the key's Deserialize impl
+        if self.datatype == DataType::Struct && self.de.next_char()? != '"' {
+            let key = self.de.parse_identifier()?;
+            return seed
+                .deserialize(BorrowedStrDeserializer::new(key))
+                .map(Some);
+        }
+        let z = seed.deserialize(&mut *self.de);
+        match z {
+            Ok(z) => Ok(Some(z)),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, JaclDeError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.de.parse_key_separator()?;
+        self.de.deserialize_value(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.datatype {
+            DataType::Struct => Some(0),
+            DataType::HashMap => None,
+            DataType::Seq => None,
+        }
+    }
+}
+
+// The entries of a map as a sequence of `(key, value)` pairs.
+struct Entries<'a, 'de: 'a> {
+    map: Separated<'a, 'de>,
+    // the input at the map's opening delimiter, where an element type that
+    // isn't a pair is reported
+    open: &'de str,
+}
+
+impl<'de, 'a> SeqAccess<'de> for Entries<'a, 'de> {
+    type Error = JaclDeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, JaclDeError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.map.separator()?;
+        self.map.de.skip_non_tokens()?;
+        let close = match self.map.datatype {
+            DataType::Struct => ')',
+            _ => '}',
+        };
+        if self.map.de.next_char()? == close {
+            // an empty map has no entry to check the element type with
+            let is_pair = Cell::new(false);
+            let _ = seed.deserialize(PairProbe(&is_pair));
+            if !is_pair.get() {
+                return Err(not_pairs(self.map.de, self.open));
+            }
+            self.map.de.parse_delim()?;
+            return Ok(None);
+        }
+        seed.deserialize(Entry {
+            map: &mut self.map,
+            open: self.open,
+            read: 0,
+        })
+        .map(Some)
+    }
+}
+
+// the error for a map read as a sequence of something other than pairs
+fn not_pairs<'de>(de: &mut Deserializer<'de>, open: &'de str) -> JaclDeError {
+    de.input = open;
+    JaclDeError::expected(de, "`[`")
+}
+
+// One entry of a map, deserialized as a two element tuple.
+struct Entry<'b, 'a, 'de: 'a> {
+    map: &'b mut Separated<'a, 'de>,
+    open: &'de str,
+    // how many of the key and value have been read
+    read: usize,
+}
+
+impl<'de> Entry<'_, '_, 'de> {
+    fn pair<V>(mut self, len: usize, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        if len != 2 {
+            return Err(not_pairs(self.map.de, self.open));
+        }
+        let value = visitor.visit_seq(&mut self)?;
+        if self.read < 2 {
+            let hint = "a map entry is a `(key, value)` pair, but its value wasn't read";
+            return Err(JaclDeError::hinted(self.map.de, hint.to_string()));
+        }
+        Ok(value)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Entry<'_, '_, 'de> {
+    type Error = JaclDeError;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        Err(not_pairs(self.map.de, self.open))
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.pair(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.pair(len, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq
+        map struct enum identifier ignored_any
+    }
+}
+
+// Reads no input, but records whether the type deserialized from it is a
+// pair, i.e. a tuple or tuple struct of two elements.
+struct PairProbe<'a>(&'a Cell<bool>);
+
+impl<'de> de::Deserializer<'de> for PairProbe<'_> {
+    type Error = JaclDeError;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::custom("not a pair"))
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, _visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.set(len == 2);
+        Err(de::Error::custom("probed"))
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq
+        map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> SeqAccess<'de> for &mut Entry<'_, '_, 'de> {
+    type Error = JaclDeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, JaclDeError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.read += 1;
+        match self.read {
+            1 => self.map.next_key_seed(seed),
+            2 => self.map.next_value_seed(seed).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::to_string;
+    use crate::structs::Value;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_struct() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            int: u32,
+            vec: Vec<String>,
+            map: HashMap<String, Test>,
+            underscore_test: u8,
+        }
+
+        let j = r#"
+            (
                 int : 1
                 vec : ["hello","world",]
                 map : {
@@ -778,29 +2825,276 @@ mod tests {
     }
 
     #[test]
-    fn test_literals() {
-        // no whitespace
-        assert_eq!(true, from_str::<bool>("true").unwrap());
-        assert_eq!(1u8, from_str::<u8>("1").unwrap());
-        assert_eq!(1.0, from_str::<f64>("1.0").unwrap());
-        assert_eq!("test", from_str::<String>(r#""test""#).unwrap());
-
-        // with whitespace on back
-        assert_eq!(true, from_str::<bool>("true      ").unwrap());
-        assert_eq!(1u8, from_str::<u8>("1      ").unwrap());
-        assert_eq!(1.0, from_str::<f64>("1.0      ").unwrap());
-        assert_eq!("test", from_str::<String>(r#""test"   "#).unwrap());
-
-        // whitespace front and back
-        assert_eq!(true, from_str::<bool>("     true   ").unwrap());
-        assert_eq!(1.0, from_str::<f64>("   1.0  ").unwrap());
-        assert_eq!(1u8, from_str::<u8>("   \n1   ").unwrap());
+    fn test_comments_in_entries() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            a: u8,
+            b: HashMap<String, u8>,
+        }
+        let expected = Test {
+            a: 1,
+            b: HashMap::from([("c".to_string(), 2)]),
+        };
+        for s in [
+            "/* x */ a : 1 b : {\"c\" : 2}",
+            "a /* x */ : 1 b /* x */ : {\"c\" /* x */ : 2}",
+            "a : /* x */ 1 b : /* x */ {\"c\" : /* x */ 2}",
+            "a/* x */:/* x */1 b/* x */:/* x */{/* x */\"c\"/* x */:/* x */2/* x */}/* x */",
+            "(a // x\n : # x\n 1 b : { \"c\" // x\n : 2 })",
+        ] {
+            assert_eq!(expected, from_str::<Test>(s).unwrap(), "{}", s);
+            assert_eq!(
+                from_str::<Value>("a : 1 b : {\"c\" : 2}").unwrap(),
+                from_str::<Value>(s).unwrap()
+            );
+        }
+        let with_equals = |s| {
+            let mut de = Deserializer::from_str(s).with_equals_separator(true);
+            Test::deserialize(&mut de)
+        };
         assert_eq!(
-            "test",
-            from_str::<String>(r#"   "test"   ,,,,,,,,,,,"#).unwrap()
+            expected,
+            with_equals("a /* x */ = /* x */ 1 b = {\"c\" /* x */ = /* x */ 2}").unwrap()
         );
+    }
 
-        // multiline string literal
+    #[test]
+    fn test_comment_at_eof() {
+        let v: Vec<u8> = vec![1, 2, 3];
+        assert_eq!(v, from_str::<Vec<u8>>("1 2 3 // done").unwrap());
+        assert_eq!(v, from_str::<Vec<u8>>("[1 2 3] //").unwrap());
+        assert_eq!(1, from_str::<u8>("1 // done").unwrap());
+    }
+
+    #[test]
+    fn test_nested_comments() {
+        let v: Vec<u8> = vec![1, 2];
+        assert_eq!(
+            v,
+            from_str::<Vec<u8>>("1 /* outer /* inner */ still commented */ 2").unwrap()
+        );
+        assert_eq!(
+            v,
+            from_str::<Vec<u8>>("/* a /* b /* c */ */ */ 1 2 /**/").unwrap()
+        );
+
+        let err = from_str::<Vec<u8>>("[1 /* outer /* inner */ 2]")
+            .expect_err("unterminated comment accepted?");
+        assert_eq!(1, err.line);
+        assert_eq!(3, err.col);
+    }
+
+    #[test]
+    fn test_hash_comments() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            name: String,
+            ports: Vec<u16>,
+        }
+
+        let j = r##"
+        # the server's name
+        name : "#not a comment" // trailing comment
+        /* the ports
+           # still inside the block comment */
+        ports : [80 # http
+                 443] # https"##;
+        assert_eq!(
+            Test {
+                name: "#not a comment".to_string(),
+                ports: vec![80, 443],
+            },
+            from_str::<Test>(j).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_radix_integers() {
+        assert_eq!(255, from_str::<u8>("0xFF").unwrap());
+        assert_eq!(0xdead_beef, from_str::<u32>("0xdead_beef").unwrap());
+        assert_eq!(0o755, from_str::<u32>("0o755").unwrap());
+        assert_eq!(0b1010, from_str::<u32>("0b1010").unwrap());
+        assert_eq!(0b1111_0000, from_str::<u32>("0b1111_0000").unwrap());
+        assert_eq!(-16, from_str::<i32>("-0x10").unwrap());
+        assert_eq!(-8, from_str::<i64>("-0o10").unwrap());
+        assert_eq!(
+            vec![0xFFFF, 8, 2],
+            from_str::<Vec<u32>>("0xFF_FF 0o10 0b10").unwrap()
+        );
+        assert_eq!(
+            crate::structs::Value::int(255),
+            from_str::<crate::structs::Value>("0xff").unwrap()
+        );
+
+        // digits outside the radix, and values outside the type's range
+        assert!(from_str::<u32>("0b102").is_err());
+        assert!(from_str::<u32>("0o78").is_err());
+        assert!(from_str::<u32>("0xFG").is_err());
+        assert!(from_str::<u32>("0x").is_err());
+        assert!(from_str::<u8>("0x100").is_err());
+        assert!(from_str::<u8>("-0x1").is_err());
+    }
+
+    #[test]
+    fn test_exponent_without_fraction() {
+        assert_eq!(1e10, from_str::<f64>("1e10").unwrap());
+        assert_eq!(1e-5, from_str::<f64>("1E-5").unwrap());
+        assert_eq!(Value::flt(1e10), from_str::<Value>("1e10").unwrap());
+        assert_eq!(Value::flt(1e-5), from_str::<Value>("1E-5").unwrap());
+        assert_eq!(Value::flt(-2e3), from_str::<Value>("-2e+3").unwrap());
+        assert_eq!(
+            Value::Seq(vec![Value::flt(1e10), Value::int(1), Value::flt(1.5e2)]),
+            from_str::<Value>("[1e10 1 1.5e2]").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_exponent_sign() {
+        assert_eq!(1e5, from_str::<f64>("1e5").unwrap());
+        assert_eq!(1e5, from_str::<f64>("1e+5").unwrap());
+        assert_eq!(1e-5, from_str::<f64>("1e-5").unwrap());
+        assert_eq!(-1.5e-5, from_str::<f64>("-1.5e-5").unwrap());
+        assert_eq!(1e5, from_str::<f64>("1.e5").unwrap());
+        assert_eq!(0.5e2, from_str::<f64>(".5e2").unwrap());
+        // the mantissa isn't limited to the range of an integer
+        assert_eq!(1e19, from_str::<f64>("10000000000000000000e0").unwrap());
+
+        for bad in &[
+            "1e--5", "1e+-5", "1e-", "1e", "1.-5", ".-5", "1e0x5", "1e5e5",
+        ] {
+            assert!(from_str::<f64>(*bad).is_err(), "{} was accepted", bad);
+            let seq = format!("[{}]", bad);
+            assert!(from_str::<Value>(&seq).is_err(), "{} was accepted", seq);
+        }
+        let err = from_str::<Vec<Value>>("[1 1e--5]").unwrap_err();
+        assert_eq!((1, 3), (err.line(), err.col()));
+    }
+
+    #[test]
+    fn test_plus_sign() {
+        assert_eq!(5, from_str::<i32>("+5").unwrap());
+        assert_eq!(5, from_str::<u8>("+5").unwrap());
+        assert_eq!(0, from_str::<i64>("+0").unwrap());
+        assert_eq!(1.5, from_str::<f64>("+1.5").unwrap());
+        assert_eq!(255, from_str::<u8>("+0xff").unwrap());
+        assert_eq!(Value::int(5), from_str::<Value>("+5").unwrap());
+        assert_eq!(Value::int(0), from_str::<Value>("+0").unwrap());
+        assert_eq!(Value::flt(1.5), from_str::<Value>("+1.5").unwrap());
+        assert_eq!(Value::flt(2e3), from_str::<Value>("+2e+3").unwrap());
+        assert_eq!(
+            Value::Seq(vec![Value::int(1), Value::int(-2), Value::flt(0.5)]),
+            from_str::<Value>("[+1 -2 +0.5]").unwrap()
+        );
+        assert!(from_str::<i32>("+-5").is_err());
+        assert!(from_str::<i32>("++5").is_err());
+        assert!(from_str::<f64>("1e--5").is_err());
+    }
+
+    #[test]
+    fn test_float_underscores() {
+        assert_eq!(1000.5, from_str::<f64>("1_000.5").unwrap());
+        assert_eq!(1.234_567, from_str::<f64>("1.234_567").unwrap());
+        assert_eq!(1.0, from_str::<f64>("1_000.0e-3").unwrap());
+        assert_eq!(1e10, from_str::<f64>("1.0e1_0").unwrap());
+        assert_eq!(1_000.0, from_str::<f32>("1_000").unwrap());
+        assert_eq!(
+            crate::structs::Value::flt(1000.25),
+            from_str::<crate::structs::Value>("1_000.2_5").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_non_finite_floats() {
+        assert_eq!(f64::INFINITY, from_str::<f64>("inf").unwrap());
+        assert_eq!(f64::INFINITY, from_str::<f64>("+Infinity").unwrap());
+        assert_eq!(f64::NEG_INFINITY, from_str::<f64>("-inf").unwrap());
+        assert_eq!(f32::NEG_INFINITY, from_str::<f32>("-INFINITY").unwrap());
+        // NaN != NaN, so check with is_nan rather than assert_eq
+        assert!(from_str::<f64>("nan").unwrap().is_nan());
+        assert!(from_str::<f64>("NaN").unwrap().is_nan());
+        assert!(from_str::<f32>("-nan").unwrap().is_nan());
+        assert!(from_str::<f64>("nancy").is_err());
+        assert!(from_str::<u8>("abc").is_err());
+
+        let v = from_str::<Vec<f64>>("[1.5 inf -inf nan]").unwrap();
+        assert_eq!(vec![1.5, f64::INFINITY, f64::NEG_INFINITY], v[..3]);
+        assert!(v[3].is_nan());
+
+        match from_str::<crate::structs::Value>("NaN").unwrap() {
+            crate::structs::Value::Literal(crate::structs::Literal::Number(
+                crate::structs::Number::Flt(f),
+            )) => assert!(f.is_nan()),
+            v => panic!("expected a float, got {}", v),
+        }
+
+        // keys that look like non-finite floats are still identifiers
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            inf: f64,
+            info: f64,
+            nan: bool,
+        }
+        assert_eq!(
+            Test {
+                inf: f64::INFINITY,
+                info: 1.0,
+                nan: true
+            },
+            from_str::<Test>("inf : inf info : 1.0 nan : true").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_uppercase_identifiers() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        #[serde(rename_all = "camelCase")]
+        struct Test {
+            user_id: u32,
+            #[serde(rename = "TcpStream")]
+            tcp_stream: bool,
+        }
+
+        assert_eq!(
+            Test {
+                user_id: 7,
+                tcp_stream: true
+            },
+            from_str::<Test>("userId : 7 TcpStream : true").unwrap()
+        );
+        assert_eq!(
+            Ok(("", "TcpStream")),
+            crate::parsing::identifier("TcpStream")
+        );
+        assert_eq!(
+            Ok((" : 1", "HTTP_2")),
+            crate::parsing::identifier("HTTP_2 : 1")
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        // no whitespace
+        assert!(from_str::<bool>("true").unwrap());
+        assert_eq!(1u8, from_str::<u8>("1").unwrap());
+        assert_eq!(1.0, from_str::<f64>("1.0").unwrap());
+        assert_eq!("test", from_str::<String>(r#""test""#).unwrap());
+
+        // with whitespace on back
+        assert!(from_str::<bool>("true      ").unwrap());
+        assert_eq!(1u8, from_str::<u8>("1      ").unwrap());
+        assert_eq!(1.0, from_str::<f64>("1.0      ").unwrap());
+        assert_eq!("test", from_str::<String>(r#""test"   "#).unwrap());
+
+        // whitespace front and back
+        assert!(from_str::<bool>("     true   ").unwrap());
+        assert_eq!(1.0, from_str::<f64>("   1.0  ").unwrap());
+        assert_eq!(1u8, from_str::<u8>("   \n1   ").unwrap());
+        assert_eq!(
+            "test",
+            from_str::<String>(r#"   "test"   ,,,,,,,,,,,"#).unwrap()
+        );
+
+        // multiline string literal
         assert_eq!(
             "test\ntest\n",
             from_str::<String>(
@@ -821,6 +3115,1765 @@ test
         assert_eq!(13, val.col);
     }
 
+    #[test]
+    fn test_char() {
+        assert_eq!('a', from_str::<char>(r#""a""#).unwrap());
+        assert_eq!('λ', from_str::<char>(r#""λ""#).unwrap());
+        assert_eq!('😀', from_str::<char>(r#"  "😀"  "#).unwrap());
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            sep: char,
+        }
+        assert_eq!(Test { sep: ',' }, from_str::<Test>(r#"sep : ",""#).unwrap());
+
+        let err = from_str::<char>(r#"  "ab""#).expect_err("multi-char string parsed as char?");
+        assert_eq!(1, err.line);
+        assert_eq!(2, err.col);
+        assert!(from_str::<char>(r#""""#).is_err());
+    }
+
+    #[test]
+    fn test_bytes() {
+        use serde_bytes::ByteBuf;
+
+        let expected = vec![0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(
+            expected,
+            from_str::<ByteBuf>("[222 173 190 239]").unwrap().into_vec()
+        );
+        assert_eq!(
+            expected,
+            from_str::<ByteBuf>("222 173 190 239").unwrap().into_vec()
+        );
+        assert_eq!(
+            expected,
+            from_str::<ByteBuf>(r#""deadbeef""#).unwrap().into_vec()
+        );
+        assert_eq!(
+            expected,
+            from_str::<ByteBuf>(r#""DEADBEEF""#).unwrap().into_vec()
+        );
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            #[serde(with = "serde_bytes")]
+            key: Vec<u8>,
+        }
+        assert_eq!(
+            Test { key: vec![1, 2] },
+            from_str::<Test>("key : [1 2]").unwrap()
+        );
+
+        assert!(from_str::<ByteBuf>("[1 256]").is_err());
+        assert!(from_str::<ByteBuf>(r#""abc""#).is_err());
+        let err = from_str::<ByteBuf>(r#"  "zz""#).expect_err("non-hex string parsed as bytes?");
+        assert_eq!(2, err.col);
+
+        // the `[...]` form is read like any other sequence
+        let mut de = Deserializer::from_str("[1,, 2]").with_strict_commas(true);
+        let err = ByteBuf::deserialize(&mut de).unwrap_err();
+        assert_eq!(Some("expected a value, found `,`"), err.hint());
+        let mut de =
+            Deserializer::from_str("[1 2]").with_options(&DeOptions::new().with_max_depth(0));
+        assert!(matches!(
+            ByteBuf::deserialize(&mut de).unwrap_err().kind(),
+            ErrorKind::DepthLimit(0)
+        ));
+        let mut de = Deserializer::from_str("[&b 1 *b]").with_anchors(true);
+        assert_eq!(
+            vec![1, 1],
+            ByteBuf::deserialize(&mut de).unwrap().into_vec()
+        );
+    }
+
+    #[test]
+    fn test_custom_err() {
+        #[derive(Debug)]
+        struct Even(u32);
+
+        impl<'de> Deserialize<'de> for Even {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                let v = u32::deserialize(deserializer)?;
+                if v % 2 == 0 {
+                    Ok(Even(v))
+                } else {
+                    Err(de::Error::custom(format!("{} is odd", v)))
+                }
+            }
+        }
+
+        assert_eq!(4, from_str::<Even>("4").unwrap().0);
+        let err = from_str::<Even>("3").expect_err("odd number accepted?");
+        assert!(matches!(err.kind(), ErrorKind::Custom(msg) if msg == "3 is odd"));
+        assert_eq!("error: 3 is odd", err.to_string());
+
+        // derived/builtin visitors report type mismatches through custom errors
+        let err =
+            from_str::<crate::structs::Number>(r#""one""#).expect_err("string parsed as number?");
+        assert!(matches!(err.kind(), ErrorKind::Custom(_)));
+    }
+
+    #[test]
+    fn test_reader() {
+        use std::io::Cursor;
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            int: u32,
+            s: String,
+        }
+
+        let reader = Cursor::new(r#"int : 1 s : "hello""#.as_bytes());
+        assert_eq!(
+            Test {
+                int: 1,
+                s: "hello".to_string()
+            },
+            from_reader::<_, Test>(reader).unwrap()
+        );
+
+        struct Broken;
+
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken pipe"))
+            }
+        }
+
+        let err = from_reader::<_, Test>(Broken).expect_err("read error was swallowed?");
+        assert!(matches!(err.kind(), ErrorKind::Io(_)));
+    }
+
+    #[test]
+    fn test_slice() {
+        let v: Vec<String> = vec!["λ".to_string(), "b".to_string()];
+        assert_eq!(
+            v,
+            from_slice::<Vec<String>>(r#""λ" "b""#.as_bytes()).unwrap()
+        );
+
+        let err =
+            from_slice::<Vec<String>>(b"\"ab\" \"\xff\"").expect_err("invalid utf-8 accepted?");
+        assert!(matches!(err.kind(), ErrorKind::InvalidUtf8(6)));
+    }
+
+    #[test]
+    fn test_from_path() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Server {
+            host: String,
+            port: u16,
+        }
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            name: String,
+            server: Server,
+        }
+        let dir = std::env::temp_dir().join(format!("serde_jacl_include_{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(
+            dir.join("main.jacl"),
+            "name : \"@include \\\"x\\\"\" // @include \"x\"\n@include \"sub/server.jacl\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("sub/server.jacl"),
+            "server : (host : \"a\" port : 80)",
+        )
+        .unwrap();
+        assert_eq!(
+            Config {
+                name: "@include \"x\"".into(),
+                server: Server {
+                    host: "a".into(),
+                    port: 80,
+                },
+            },
+            from_path(dir.join("main.jacl")).unwrap()
+        );
+
+        fs::write(dir.join("self.jacl"), "x : 1\n  @include \"self.jacl\"").unwrap();
+        let err = from_path::<Value>(dir.join("self.jacl")).unwrap_err();
+        assert_eq!((2, 2), (err.line(), err.col()));
+        assert_eq!(Some("`self.jacl` includes itself"), err.hint());
+        // a cycle through a relative path, caught where it closes
+        fs::write(dir.join("sub/a.jacl"), "@include \"../b.jacl\"").unwrap();
+        fs::write(dir.join("b.jacl"), "@include \"sub/a.jacl\"").unwrap();
+        let err = from_path::<Value>(dir.join("b.jacl")).unwrap_err();
+        assert_eq!(Some("`../b.jacl` includes itself"), err.hint());
+        assert_eq!(Some(dir.join("sub/a.jacl").as_path()), err.file());
+
+        fs::write(dir.join("missing.jacl"), "x : 1 @include \"nope.jacl\"").unwrap();
+        let err = from_path::<Value>(dir.join("missing.jacl")).unwrap_err();
+        assert_eq!((1, 6), (err.line(), err.col()));
+        assert!(err.hint().unwrap().starts_with("can't include `nope.jacl`"));
+        assert!(matches!(
+            from_path::<Value>(dir.join("none.jacl"))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Io(_)
+        ));
+        // includes are only resolved when reading from a path
+        assert!(from_str::<Value>("@include \"sub/server.jacl\"").is_err());
+
+        // errors point into the file they're in
+        fs::write(dir.join("sub/bad.jacl"), "a : 1\nb : [1 2}").unwrap();
+        fs::write(dir.join("outer.jacl"), "x : 1\n@include \"sub/bad.jacl\"").unwrap();
+        let err = from_path::<Value>(dir.join("outer.jacl")).unwrap_err();
+        assert_eq!((2, 8), (err.line(), err.col()));
+        assert_eq!("b : [1 2}", err.line_str());
+        assert_eq!(Some(dir.join("sub/bad.jacl").as_path()), err.file());
+        assert!(err.to_string().starts_with(&format!(
+            "error in {} at line: 2 col: 8",
+            dir.join("sub/bad.jacl").display()
+        )));
+        fs::write(
+            dir.join("after.jacl"),
+            "@include \"sub/server.jacl\" // server\nname : ]",
+        )
+        .unwrap();
+        let err = from_path::<Value>(dir.join("after.jacl")).unwrap_err();
+        assert_eq!((2, 7), (err.line(), err.col()));
+        assert_eq!("name : ]", err.line_str());
+        assert_eq!(Some(dir.join("after.jacl").as_path()), err.file());
+
+        // a comment at the end of an included file ends with it
+        fs::write(dir.join("comment.jacl"), "a : 1 // c").unwrap();
+        fs::write(dir.join("inline.jacl"), "@include \"comment.jacl\" b : 2").unwrap();
+        assert_eq!(
+            HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]),
+            from_path::<HashMap<String, u32>>(dir.join("inline.jacl")).unwrap()
+        );
+
+        // options apply to included files too
+        fs::write(dir.join("nested.jacl"), "a : [[1]]").unwrap();
+        fs::write(dir.join("deep.jacl"), "@include \"nested.jacl\"").unwrap();
+        let options = DeOptions::new().with_max_depth(1);
+        let err = from_path_with_options::<Value>(dir.join("deep.jacl"), &options).unwrap_err();
+        assert_eq!(&ErrorKind::DepthLimit(1), err.kind());
+        assert_eq!(Some(dir.join("nested.jacl").as_path()), err.file());
+        fs::write(dir.join("commas.jacl"), "a : [1,, 2]").unwrap();
+        let options = DeOptions::new().with_strict_commas(true);
+        assert!(from_path::<Value>(dir.join("commas.jacl")).is_ok());
+        assert!(from_path_with_options::<Value>(dir.join("commas.jacl"), &options).is_err());
+
+        // `@include` has to be the whole word
+        fs::write(dir.join("word.jacl"), "@includes \"sub/server.jacl\"").unwrap();
+        let err = from_path::<Value>(dir.join("word.jacl")).unwrap_err();
+        assert_eq!((1, 0), (err.line(), err.col()));
+        assert!(!err.hint().unwrap_or("").starts_with("can't include"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_unit() {
+        from_str::<()>("()").unwrap();
+        from_str::<()>("  ( ) ").unwrap();
+        from_str::<()>("null").unwrap();
+        assert!(from_str::<()>("(1)").is_err());
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Nothing;
+        assert_eq!(Nothing, from_str::<Nothing>("()").unwrap());
+        assert_eq!(Nothing, from_str::<Nothing>("null").unwrap());
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            version: Nothing,
+            unit: (),
+            int: u8,
+        }
+        assert_eq!(
+            Test {
+                version: Nothing,
+                unit: (),
+                int: 1
+            },
+            from_str::<Test>("version : () unit : null int : 1").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_newtype() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Meters(f64);
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Id(String);
+
+        assert_eq!(Meters(3.5), from_str::<Meters>("3.5").unwrap());
+        assert_eq!(Id("abc".to_string()), from_str::<Id>(r#""abc""#).unwrap());
+        assert_eq!(
+            vec![Meters(1.0), Meters(2.0), Meters(3.0)],
+            from_str::<Vec<Meters>>("[1.0 2.0 3.0]").unwrap()
+        );
+        assert_eq!(
+            vec![Id("a".to_string()), Id("b".to_string())],
+            from_str::<Vec<Id>>(r#"["a" "b"]"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_128() {
+        assert_eq!(
+            i128::MAX,
+            from_str::<i128>("170141183460469231731687303715884105727").unwrap()
+        );
+        assert_eq!(
+            i128::MIN,
+            from_str::<i128>("-170141183460469231731687303715884105728").unwrap()
+        );
+        assert_eq!(
+            u128::MAX,
+            from_str::<u128>("340282366920938463463374607431768211455").unwrap()
+        );
+        assert_eq!(
+            i64::MAX as u128 + 1,
+            from_str::<u128>("9_223_372_036_854_775_808").unwrap()
+        );
+        assert!(from_str::<u128>("340282366920938463463374607431768211456").is_err());
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            id: u128,
+        }
+        assert_eq!(
+            Test {
+                id: u64::MAX as u128 * 2
+            },
+            from_str::<Test>("id : 36893488147419103230").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let deep = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+        let err = from_str::<Value>(&deep).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::DepthLimit(DEFAULT_MAX_DEPTH)
+        ));
+        assert_eq!(1, err.line);
+        assert_eq!(DEFAULT_MAX_DEPTH + 1, err.col);
+
+        let nested = "[[[0]]]";
+        assert!(from_str_with_depth::<Value>(nested, 3).is_ok());
+        let err = from_str_with_depth::<Value>(nested, 2).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::DepthLimit(2)));
+        let err = from_str_with_depth::<Value>("a : (b : {\"c\" : 0})", 2).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::DepthLimit(2)));
+
+        // depth is released when a container closes
+        assert!(from_str_with_depth::<Value>("[[0] [0] [0]]", 2).is_ok());
+        let mut de = Deserializer::from_str("[[0]]").with_max_depth(1);
+        assert!(Value::deserialize(&mut de).is_err());
+    }
+
+    #[test]
+    fn test_string_errors() {
+        // not a string
+        let err = from_str::<String>("0").unwrap_err();
+        assert_eq!((1, 0), (err.line, err.col));
+        // unterminated, pointing at the opening quote
+        let err = from_str::<Vec<String>>("[\"a\"\n  \"b]").unwrap_err();
+        assert_eq!((2, 2), (err.line, err.col));
+        // bad escape, pointing at the backslash
+        let err = from_str::<String>(r#""ab\q""#).unwrap_err();
+        assert_eq!((1, 3), (err.line, err.col));
+    }
+
+    #[test]
+    fn test_escapes() {
+        for (escape, c) in [
+            (r#"\""#, '"'),
+            (r"\\", '\\'),
+            (r"\/", '/'),
+            (r"\b", '\u{8}'),
+            (r"\f", '\u{c}'),
+            (r"\n", '\n'),
+            (r"\r", '\r'),
+            (r"\t", '\t'),
+            (r"\u0009", '\t'),
+        ] {
+            let input = format!("\"a{}b\"", escape);
+            assert_eq!(format!("a{}b", c), from_str::<String>(&input).unwrap());
+        }
+        // literal control characters are kept, and need no copy
+        for raw in ["a\tb", "a\r\nb", "a\u{0}\u{1b}b"] {
+            let input = format!("\"{}\"", raw);
+            assert_eq!(raw, from_str::<String>(&input).unwrap());
+            let (s, _) = from_str_remaining::<&str>(&input).unwrap();
+            assert_eq!(raw, s);
+        }
+        for bad in [r#""\a""#, r#""\x41""#, r#""\'""#, r#""\ ""#] {
+            let err = from_str::<String>(bad).unwrap_err();
+            assert_eq!((1, 1), (err.line(), err.col()));
+        }
+    }
+
+    #[test]
+    fn test_unicode_escapes() {
+        assert_eq!("\u{e9}", from_str::<String>(r#""\u00e9""#).unwrap());
+        assert_eq!("\u{e9}", from_str::<String>(r#""\u{e9}""#).unwrap());
+        assert_eq!("\u{1F600}", from_str::<String>(r#""\u{1F600}""#).unwrap());
+        assert_eq!(
+            "\u{1F600}",
+            from_str::<String>(r#""\uD83D\uDE00""#).unwrap()
+        );
+        assert_eq!(
+            "a\"\\/\u{8}\u{c}\n\r\tb",
+            from_str::<String>(r#""a\"\\\/\b\f\n\r\tb""#).unwrap()
+        );
+        // raw newlines are kept as they are
+        assert_eq!("a\nb", from_str::<String>("\"a\nb\"").unwrap());
+
+        // invalid code points and lone surrogates
+        let err = from_str::<String>(r#""ab\u{110000}""#).unwrap_err();
+        assert_eq!((1, 3), (err.line, err.col));
+        assert!(from_str::<String>(r#""\uD83D""#).is_err());
+        assert!(from_str::<String>(r#""\uDE00""#).is_err());
+        assert!(from_str::<String>(r#""\uD83Dx""#).is_err());
+        assert!(from_str::<String>(r#""\u{D800}""#).is_err());
+        // malformed escapes
+        assert!(from_str::<String>(r#""\u00""#).is_err());
+        assert!(from_str::<String>(r#""\u{}""#).is_err());
+        assert!(from_str::<String>(r#""\u{1234567}""#).is_err());
+        assert!(from_str::<String>(r#""\u{12""#).is_err());
+        assert!(from_str::<String>("\"\\u00\u{e9}\"").is_err());
+    }
+
+    #[test]
+    fn test_borrowed_str() {
+        #[derive(Deserialize)]
+        struct Test<'a> {
+            name: &'a str,
+            label: Cow<'a, str>,
+        }
+        let input = r#"name : "abc" label : "a\"b""#;
+        let mut de = Deserializer::from_str(input);
+        let test = Test::deserialize(&mut de).unwrap();
+        assert_eq!("abc", test.name);
+        assert_eq!("a\"b", test.label);
+        // the escape-free string points into the input rather than a copy
+        let range = input.as_ptr() as usize..input.as_ptr() as usize + input.len();
+        assert!(range.contains(&(test.name.as_ptr() as usize)));
+
+        // escaped strings can't be borrowed
+        let mut de = Deserializer::from_str(r#""a\nb""#);
+        assert!(<&str>::deserialize(&mut de).is_err());
+    }
+
+    #[test]
+    fn test_error_location() {
+        // the remaining input also appears earlier in the document
+        let err = from_str::<Vec<u8>>("[1 1 1 1]1 1]").unwrap_err();
+        assert_eq!((1, 9), (err.line, err.col));
+        let err = from_str::<Vec<u8>>("[1 1\n1 x]").unwrap_err();
+        assert_eq!((2, 2), (err.line, err.col));
+        // columns count chars, not bytes
+        let err = from_str::<Vec<String>>(r#"["λ" "λ" x]"#).unwrap_err();
+        assert_eq!((1, 9), (err.line, err.col));
+        assert_eq!(r#"["λ" "λ" x]"#, err.line_str);
+    }
+
+    #[test]
+    fn test_expected() {
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Test {
+            int: u32,
+            seq: Vec<u32>,
+        }
+
+        let hint = |s: &str| from_str::<Test>(s).unwrap_err().hint.unwrap();
+        assert_eq!("expected an integer, found `)`", hint("(int : )"));
+        assert_eq!("expected `:`, found `1`", hint("(int 1)"));
+        assert_eq!("expected `[`, found `{`", hint("(int : 1 seq : {})"));
+        assert_eq!(
+            "expected an integer, found `x`",
+            hint("(int : 1 seq : [x])")
+        );
+        assert_eq!("expected an integer, found end of input", hint("(int : "));
+        assert_eq!(
+            "expected a field name or `)`, found `]`",
+            hint("(int : 1 ]")
+        );
+        assert_eq!(
+            "expected an integer, found a string",
+            hint(r#"(int : "1")"#)
+        );
+
+        let err = from_str::<Test>("(int : 1\nseq : [1 a])").unwrap_err();
+        assert_eq!(
+            "error at line: 2 col: 9\nseq : [1 a])\n---------^\nexpected an integer, found `a`\n",
+            err.to_string()
+        );
+        let err = from_str::<HashMap<String, u32>>(r#"{"a" : 1 )"#).unwrap_err();
+        assert_eq!(
+            Some("expected a key or `}`, found `)`"),
+            err.hint.as_deref()
+        );
+    }
+
+    #[test]
+    fn test_container_mismatch() {
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Inner {
+            a: u32,
+        }
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Test {
+            inner: Inner,
+            map: HashMap<String, u32>,
+        }
+
+        let err = from_str::<Test>("inner : {a : 1}").unwrap_err();
+        assert_eq!((1, 8), (err.line(), err.col()));
+        assert_eq!(Some("expected struct `(...)`, found map `{`"), err.hint());
+        let err = from_str::<Test>("inner : (a : 1) map : [1]").unwrap_err();
+        assert_eq!((1, 22), (err.line(), err.col()));
+        assert_eq!(Some("expected map `{...}`, found seq `[`"), err.hint());
+        let err = from_str::<Test>("inner : [1]").unwrap_err();
+        assert_eq!(Some("expected struct `(...)`, found seq `[`"), err.hint());
+        // anything else isn't a mix-up of brackets
+        let err = from_str::<Test>("inner : 1").unwrap_err();
+        assert_eq!(Some("expected `(`, found `1`"), err.hint());
+    }
+
+    #[test]
+    fn test_empty_input() {
+        for s in &["", "   ", "\n\t", "// comment\n", "/* a */ # b"] {
+            let err = from_str::<Value>(*s).unwrap_err();
+            assert_eq!(Some("expected a value, found end of input"), err.hint());
+            let err = from_str::<Vec<u32>>(*s).unwrap_err();
+            assert_eq!(Some("expected a value, found end of input"), err.hint());
+            let err = from_borrowed_str::<Option<&str>>(s).unwrap_err();
+            assert_eq!(Some("expected a value, found end of input"), err.hint());
+        }
+        // the error is where the value should have started
+        let err = from_str::<u32>("// nothing here\n  ").unwrap_err();
+        assert_eq!((2, 2), (err.line(), err.col()));
+        assert_eq!(&ErrorKind::Syntax, err.kind());
+        // a struct without parentheses can still be empty
+        #[derive(Deserialize, Debug, PartialEq, Default)]
+        struct Test {
+            #[serde(default)]
+            a: u32,
+        }
+        assert_eq!(Test::default(), from_str_struct::<Test>("").unwrap());
+    }
+
+    #[test]
+    fn test_unclosed() {
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Server {
+            host: String,
+            ports: Vec<u16>,
+        }
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Config {
+            name: String,
+            server: Server,
+        }
+        let s = "name : \"a\"\nserver : (\n  host : \"b\"\n  ports : [80]\n";
+        let err = from_str::<Config>(s).unwrap_err();
+        assert_eq!((2, 9), (err.line(), err.col()));
+        assert_eq!(Some("unclosed `(`"), err.hint());
+
+        // the innermost unclosed container is reported
+        let s = "name : \"a\" server : (host : \"b\" ports : [80 443";
+        let err = from_str::<Config>(s).unwrap_err();
+        assert_eq!((1, 40), (err.line(), err.col()));
+        assert_eq!(Some("unclosed `[`"), err.hint());
+        let err = from_str::<Value>("[1 {\"a\" : 1}\n  {\"b\" : [2]").unwrap_err();
+        assert_eq!((2, 2), (err.line(), err.col()));
+        assert_eq!(Some("unclosed `{`"), err.hint());
+        let err = from_str::<Vec<(String, u32)>>("{\"a\" : 1").unwrap_err();
+        assert_eq!(Some("unclosed `{`"), err.hint());
+        // the implicit top level container needs no closer
+        let err = from_str::<Config>("name : \"a\"").unwrap_err();
+        assert_ne!(Some("unclosed `(`"), err.hint());
+    }
+
+    #[test]
+    fn test_error_eq() {
+        let err = from_str::<Vec<u8>>("[1 x]").unwrap_err();
+        assert_eq!(err, err.clone());
+        assert_eq!(err, from_str::<Vec<u8>>("[1 x]").unwrap_err());
+        assert_ne!(err, from_str::<Vec<u8>>("[1  x]").unwrap_err());
+        assert_ne!(err, from_str::<Vec<u8>>("[1 -]").unwrap_err());
+
+        let io = JaclDeError::from(io::Error::other("a"));
+        assert_eq!(io, io.clone());
+        assert_eq!((0, 0, ""), (io.line(), io.col(), io.line_str()));
+    }
+
+    #[test]
+    fn test_crlf() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            a: u8,
+            b: Vec<u8>,
+        }
+
+        let input = "a : 1 // one\r\n# two\r\nb : [1\r\n2] /* three\r\n */\r\n";
+        assert_eq!(
+            Test {
+                a: 1,
+                b: vec![1, 2]
+            },
+            from_str::<Test>(input).unwrap()
+        );
+
+        let err = from_str::<Test>("a : 1 // one\r\nb : [1 x]\r\n").unwrap_err();
+        assert_eq!((2, 7), (err.line(), err.col()));
+        assert_eq!("b : [1 x]", err.line_str());
+        assert_eq!(
+            "error at line: 2 col: 7\nb : [1 x]\n-------^\nexpected an integer, found `x`\n",
+            err.to_string()
+        );
+        let err = from_str::<Test>("a : 1\nb : [1 x]\na").unwrap_err();
+        assert_eq!("b : [1 x]", err.line_str());
+    }
+
+    #[test]
+    fn test_large_input() {
+        // errors are created while probing every key, so this is quadratic
+        // if each one rescans the input
+        let line = format!("(a : 1 b : \"x\") // {}\n", "-".repeat(100));
+        let mut input = line.repeat(20_000);
+        input.insert(0, '[');
+        input.push_str("(a : 1 b : x y)]");
+        assert!(input.len() > 2_000_000);
+        let err = from_str::<Vec<Value>>(&input).unwrap_err();
+        // the missing `:` is reported at the `)` found in its place
+        assert_eq!((20_001, 14), (err.line, err.col));
+        assert_eq!("(a : 1 b : x y)]", err.line_str);
+    }
+
+    #[test]
+    fn test_from_str_remaining() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            id: u32,
+        }
+        let input = "(id : 1) // first\n(id : 2)\n";
+        let (first, rest) = from_str_remaining::<Test>(input).unwrap();
+        assert_eq!(Test { id: 1 }, first);
+        assert_eq!("(id : 2)\n", rest);
+        let (second, rest) = from_str_remaining::<Test>(rest).unwrap();
+        assert_eq!(Test { id: 2 }, second);
+        assert_eq!("", rest);
+
+        // literals aren't wrapped into a sequence
+        let (num, rest) = from_str_remaining::<u32>("1 2").unwrap();
+        assert_eq!((1, "2"), (num, rest));
+
+        // bare entries are wrapped like in from_str, up to the end of input
+        let (first, rest) = from_str_remaining::<Test>(
+            "id : 1 // only
+",
+        )
+        .unwrap();
+        assert_eq!((Test { id: 1 }, ""), (first, rest));
+        let (map, rest) = from_str_remaining::<HashMap<String, u32>>(r#""a" : 1"#).unwrap();
+        assert_eq!((HashMap::from([("a".to_string(), 1)]), ""), (map, rest));
+        assert!(from_str_remaining::<Test>("id : 1 (id : 2)").is_err());
+    }
+
+    #[test]
+    fn test_from_str_struct() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Config {
+            name: String,
+            port: u16,
+            #[serde(default)]
+            inf: bool,
+        }
+
+        let expected = Config {
+            name: "x".to_string(),
+            port: 8080,
+            inf: false,
+        };
+        let file = "// server\nname : \"x\"\nport : 8080\n";
+        assert_eq!(expected, from_str_struct::<Config>(file).unwrap());
+        assert_eq!(expected, from_str::<Config>(file).unwrap());
+        // from_str guesses a map here
+        let file = "\"name\" : \"x\"\nport : 8080";
+        assert!(from_str::<Config>(file).is_err());
+        assert_eq!(expected, from_str_struct::<Config>(file).unwrap());
+
+        assert!(from_str_struct::<Config>("").is_err());
+        let err = from_str_struct::<Config>("name : \"x\" port : 1 )").unwrap_err();
+        assert_eq!((1, 21), (err.line(), err.col()));
+        assert!(from_str::<Config>("name : \"x\" port : 1 )").is_err());
+    }
+
+    #[test]
+    fn test_end() {
+        assert_eq!(1, from_str::<u8>("1 // trailing comment\n").unwrap());
+        assert_eq!(1, from_str::<u8>("/* a */ 1 /* b */ # c").unwrap());
+        assert_eq!(vec![1, 2], from_str::<Vec<u8>>("[1 2] // c\n\n").unwrap());
+        let err = from_str::<Vec<u8>>("[1] // c\n2").unwrap_err();
+        assert_eq!((2, 0), (err.line(), err.col()));
+        assert_eq!(Some("expected end of input, found `2`"), err.hint());
+
+        let mut de = Deserializer::new(None, "[1] x", None);
+        Vec::<u8>::deserialize(&mut de).unwrap();
+        assert!(de.end().is_err());
+        let mut de = Deserializer::new(None, "[1] /* */", None);
+        Vec::<u8>::deserialize(&mut de).unwrap();
+        assert!(de.end().is_ok());
+    }
+
+    #[test]
+    fn test_env_interpolation() {
+        std::env::set_var("SERDE_JACL_TEST_HOME", "/home/a");
+        std::env::remove_var("SERDE_JACL_TEST_UNSET");
+        let parse = |s| {
+            let mut de = Deserializer::new(None, s, None).with_env_interpolation(true);
+            String::deserialize(&mut de)
+        };
+        assert_eq!(
+            "/home/a/config",
+            parse("\"${SERDE_JACL_TEST_HOME}/config\"").unwrap()
+        );
+        assert_eq!("$5 and ${x}", parse("\"$5 and $${x}\"").unwrap());
+        assert_eq!("$", parse("\"$$\"").unwrap());
+
+        let err = parse(" \"${SERDE_JACL_TEST_UNSET}\"").unwrap_err();
+        assert_eq!((1, 1), (err.line(), err.col()));
+        assert_eq!(
+            Some("environment variable `SERDE_JACL_TEST_UNSET` isn't set"),
+            err.hint()
+        );
+        assert!(parse("\"${SERDE_JACL_TEST_HOME\"").is_err());
+        // off by default
+        assert_eq!(
+            "${SERDE_JACL_TEST_HOME}",
+            from_str::<String>("\"${SERDE_JACL_TEST_HOME}\"").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            name: String,
+            sizes: Vec<u32>,
+        }
+        let strict = DeOptions::new()
+            .with_strict_commas(true)
+            .with_deny_unknown_fields(true);
+        let expected = Config {
+            name: "a".into(),
+            sizes: vec![1, 2],
+        };
+        assert_eq!(
+            expected,
+            from_str_with_options("name : \"a\", sizes : [1, 2]", &strict).unwrap()
+        );
+        assert!(from_str_with_options::<Config>("name : \"a\", sizes : [1, 2,]", &strict).is_err());
+        assert!(
+            from_str_with_options::<Config>("name : \"a\" sizes : [1 2] port : 1", &strict)
+                .is_err()
+        );
+        assert!(from_str_with_options::<Config>("name = \"a\" sizes = [1 2]", &strict).is_err());
+
+        let lenient = DeOptions::new()
+            .with_equals_separator(true)
+            .with_max_depth(2);
+        assert_eq!(
+            expected,
+            from_str_with_options("name = \"a\" sizes = [1 2]", &lenient).unwrap()
+        );
+        let err = from_str_with_options::<Value>("[[[1]]]", &lenient).unwrap_err();
+        assert_eq!(&ErrorKind::DepthLimit(2), err.kind());
+
+        // keys end at an `=` with no space before it
+        let checked = DeOptions::new()
+            .with_equals_separator(true)
+            .with_deny_unknown_fields(true)
+            .with_deny_duplicate_keys(true);
+        assert_eq!(
+            expected,
+            from_str_with_options("(name=\"a\" sizes=[1 2])", &checked).unwrap()
+        );
+        let err =
+            from_str_with_options::<Config>("(name=\"a\" sizes=[] a=1)", &checked).unwrap_err();
+        assert_eq!(
+            Some("unknown field `a`, expected one of `name`, `sizes`"),
+            err.hint()
+        );
+        let err = from_str_with_options::<HashMap<String, u32>>("(a=1 a=2)", &checked).unwrap_err();
+        assert_eq!(Some("duplicate key `a`"), err.hint());
+    }
+
+    #[test]
+    fn test_ignored_any() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            name: String,
+        }
+        let expected = Config { name: "a".into() };
+        let s = r#"skipped : { "k\n" : [1 -2.5 0xff nan null (x : "y")] }
+            name : "a" big : 123456789012345678901234567890 word : abc"#;
+        assert_eq!(expected, from_str::<Config>(s).unwrap());
+
+        // ignored values aren't built, so they aren't limited in depth
+        let deep = format!("{}{}", "[".repeat(1000), "]".repeat(1000));
+        let s = format!("skipped : {} name : \"a\"", deep);
+        assert_eq!(expected, from_str::<Config>(s).unwrap());
+
+        let err = from_str::<Config>("skipped : [1 2} name : \"a\"").unwrap_err();
+        assert_eq!(Some("expected `]`, found `}`"), err.hint());
+        assert!(from_str::<Config>("skipped : [1 2 name : \"a\"").is_err());
+        assert!(from_str::<Config>("skipped : [1 @] name : \"a\"").is_err());
+        assert!(from_str::<Config>("skipped : \"open name : \"a\"").is_err());
+
+        let mut de = Deserializer::new(None, "[1, 2] , 3", None).with_strict_commas(true);
+        serde::de::IgnoredAny::deserialize(&mut de).unwrap();
+        assert_eq!(", 3", de.remaining());
+
+        // ignored values are checked like any other
+        for skipped in [
+            "{1 2 3}",
+            "[1 : 2]",
+            "(: : :)",
+            "{ \"a\" }",
+            "(a : )",
+            "{[1] : 2}",
+        ] {
+            let s = format!("skipped : {} name : \"a\"", skipped);
+            assert!(from_str::<Config>(&s).is_err(), "{}", skipped);
+            assert!(from_str::<Value>(skipped).is_err(), "{}", skipped);
+        }
+        let err = from_str::<Config>("skipped : (: 1) name : \"a\"").unwrap_err();
+        assert_eq!(Some("expected a field name or `)`, found `:`"), err.hint());
+        let err = from_str::<Config>("skipped : {\"a\" 1} name : \"a\"").unwrap_err();
+        assert_eq!(Some("expected `:`, found `1`"), err.hint());
+        let err = from_str::<Config>("skipped : [1 : 2] name : \"a\"").unwrap_err();
+        assert_eq!(Some("expected `]`, found `:`"), err.hint());
+        let s = r#"skipped : {"a" : (b : [1 2]) 1 : -2.5} name : "a""#;
+        assert_eq!(expected, from_str::<Config>(s).unwrap());
+
+        // with the options they're read with
+        let strict = DeOptions::new().with_strict_commas(true);
+        let s = r#"skipped : {"a" : [1, 2], "b" : 3}, name : "a""#;
+        assert_eq!(
+            expected,
+            from_str_with_options::<Config>(s, &strict).unwrap()
+        );
+        for skipped in ["[1,,2]", "[1, 2,]", "[,1]", "(a : 1,, b : 2)"] {
+            let s = format!("skipped : {} name : \"a\"", skipped);
+            assert!(from_str::<Config>(&s).is_ok(), "{}", skipped);
+            assert!(
+                from_str_with_options::<Config>(&s, &strict).is_err(),
+                "{}",
+                skipped
+            );
+        }
+        let equals = DeOptions::new().with_equals_separator(true);
+        let s = r#"skipped = (a = [1] "b" : 2) name = "a""#;
+        assert_eq!(
+            expected,
+            from_str_with_options::<Config>(s, &equals).unwrap()
+        );
+        assert!(from_str::<Config>(s).is_err());
+    }
+
+    #[test]
+    fn test_render_with_context() {
+        let src = "[1 2\n 3 x\n 4]";
+        let err = from_str::<Vec<u32>>(src).unwrap_err();
+        assert_eq!(
+            "error at line: 2 col: 3\n\
+             1 | [1 2\n\
+             2 |  3 x\n\
+            \x20 |    ^\n\
+             3 |  4]\n\
+             expected an integer, found `x`\n",
+            err.render_with_context(src)
+        );
+
+        // no line before the first or after the last
+        let src = "[x]";
+        let err = from_str::<Vec<u32>>(src).unwrap_err();
+        assert_eq!(
+            "error at line: 1 col: 1\n1 | [x]\n  |  ^\nexpected an integer, found `x`\n",
+            err.render_with_context(src)
+        );
+
+        // the gutter is as wide as the largest line number
+        let src = format!("{}[1\nx]", "\n".repeat(9));
+        let err = from_str::<Vec<u32>>(src.as_str()).unwrap_err();
+        assert!(err
+            .render_with_context(&src)
+            .contains("\n10 | [1\n11 | x]\n   | ^\n"));
+
+        let err = <JaclDeError as de::Error>::custom("bad");
+        assert_eq!(err.to_string(), err.render_with_context("a : 1"));
+    }
+
+    #[test]
+    fn test_map_as_entries() {
+        let s = "{\"z\" : 1 \"a\" : 2 \"m\" : 3}";
+        assert_eq!(
+            vec![
+                ("z".to_string(), 1),
+                ("a".to_string(), 2),
+                ("m".to_string(), 3)
+            ],
+            from_str::<Vec<(String, i64)>>(s).unwrap()
+        );
+        assert_eq!(
+            Vec::<(String, i64)>::new(),
+            from_str::<Vec<(String, i64)>>("{}").unwrap()
+        );
+        let mut de =
+            Deserializer::new(None, "{\"a\" : 1, \"b\" : 2}", None).with_strict_commas(true);
+        assert_eq!(
+            vec![("a".to_string(), 1), ("b".to_string(), 2)],
+            Vec::<(String, i64)>::deserialize(&mut de).unwrap()
+        );
+        // sequences are still sequences
+        assert_eq!(
+            vec![("a".to_string(), 1)],
+            from_str::<Vec<(String, i64)>>("[[\"a\" 1]]").unwrap()
+        );
+        assert!(from_str::<Vec<(String, i64)>>("{\"a\" : \"b\"}").is_err());
+        assert!(from_str::<Vec<(String, i64)>>("{\"a\" 1}").is_err());
+        assert!(from_str::<Vec<i64>>("{\"a\" : 1}").is_err());
+
+        // structs, and the implicit struct or map at the top level
+        let expected = vec![
+            ("header".to_string(), "a".to_string()),
+            ("header".to_string(), "b".to_string()),
+        ];
+        let entries = |s: &str| from_str::<Vec<(String, String)>>(s).unwrap();
+        assert_eq!(expected, entries("header : \"a\"\nheader : \"b\""));
+        assert_eq!(expected, entries("(header : \"a\" header : \"b\")"));
+        assert_eq!(expected, entries("\"header\" : \"a\" \"header\" : \"b\""));
+        assert_eq!(Vec::<(String, String)>::new(), entries("()"));
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Request {
+            headers: Vec<(String, String)>,
+        }
+        assert_eq!(
+            Request { headers: expected },
+            from_str("headers : (header : \"a\" header : \"b\")").unwrap()
+        );
+        assert!(from_str::<Vec<u32>>("a : 1").is_err());
+
+        // only pairs are read from a map, even an empty one
+        let err = from_str::<Vec<u32>>("{}").unwrap_err();
+        assert_eq!(Some("expected `[`, found `{`"), err.hint());
+        assert!(from_str::<Vec<(String,)>>("{\"a\" : 1}").is_err());
+        assert!(from_str::<Vec<(String, i64, i64)>>("{}").is_err());
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Pair(String, i64);
+        assert_eq!(
+            vec![Pair("a".to_string(), 1)],
+            from_str::<Vec<Pair>>("{\"a\" : 1}").unwrap()
+        );
+        // a pair that stops after the key
+        #[derive(Debug)]
+        struct KeyOnly;
+        impl<'de> Deserialize<'de> for KeyOnly {
+            fn deserialize<D: de::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                struct KeyVisitor;
+                impl<'de> Visitor<'de> for KeyVisitor {
+                    type Value = KeyOnly;
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str("a key")
+                    }
+                    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<KeyOnly, A::Error> {
+                        seq.next_element::<String>()?;
+                        Ok(KeyOnly)
+                    }
+                }
+                d.deserialize_tuple(2, KeyVisitor)
+            }
+        }
+        let err = from_str::<Vec<KeyOnly>>("{\"a\" : 1}").unwrap_err();
+        assert_eq!(
+            Some("a map entry is a `(key, value)` pair, but its value wasn't read"),
+            err.hint()
+        );
+
+        // repeated keys are all kept, unlike in a map
+        let s = "{\"a\" : 1 \"b\" : 2 \"a\" : 3}";
+        assert_eq!(
+            vec![
+                ("a".to_string(), 1),
+                ("b".to_string(), 2),
+                ("a".to_string(), 3)
+            ],
+            from_str::<Vec<(String, i64)>>(s).unwrap()
+        );
+        assert_eq!(
+            HashMap::from([("a".to_string(), 3), ("b".to_string(), 2)]),
+            from_str::<HashMap<String, i64>>(s).unwrap()
+        );
+        let mut de = Deserializer::from_str(s).with_deny_duplicate_keys(true);
+        let err = Vec::<(String, i64)>::deserialize(&mut de).unwrap_err();
+        assert_eq!(Some("duplicate key `a`"), err.hint());
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_indexmap() {
+        use indexmap::IndexMap;
+
+        let s = "{\"z\" : 1 \"a\" : [true] \"m\" : {\"k\" : null}}";
+        let map = from_str::<IndexMap<String, Value>>(s).unwrap();
+        assert_eq!(vec!["z", "a", "m"], map.keys().collect::<Vec<_>>());
+        let out = to_string(&map).unwrap();
+        assert_eq!("{\"z\":1 \"a\":[true] \"m\":{\"k\":null}}", out);
+        assert_eq!(map, from_str::<IndexMap<String, Value>>(out).unwrap());
+    }
+
+    #[test]
+    fn test_from_borrowed_str() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Cfg<'a> {
+            name: &'a str,
+            #[serde(borrow)]
+            tags: Vec<&'a str>,
+            mode: &'a str,
+        }
+        let s = String::from("name : \"jacl\" tags : [\"a\" \"b\"] mode : \"fast\"");
+        let cfg: Cfg = from_borrowed_str(&s).unwrap();
+        assert_eq!(
+            Cfg {
+                name: "jacl",
+                tags: vec!["a", "b"],
+                mode: "fast",
+            },
+            cfg
+        );
+        // the fields point into the input rather than at copies
+        assert!(s.as_bytes().as_ptr_range().contains(&cfg.name.as_ptr()));
+
+        assert!(from_borrowed_str::<Cfg>("name : \"a\\n\" tags : [] mode : \"fast\"").is_err());
+        assert!(from_borrowed_str::<Cfg>("name : \"a\" tags : [] mode : \"fast\" x").is_err());
+    }
+
+    #[test]
+    fn test_flatten() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Metadata {
+            id: u32,
+            tags: Vec<String>,
+        }
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Record {
+            name: String,
+            #[serde(flatten)]
+            meta: Metadata,
+        }
+        let expected = Record {
+            name: "a".into(),
+            meta: Metadata {
+                id: 7,
+                tags: vec!["x".into()],
+            },
+        };
+        let s = "name : \"a\" id : 7 tags : [\"x\"]";
+        assert_eq!(expected, from_str::<Record>(s).unwrap());
+        assert_eq!(
+            vec![expected],
+            from_str::<Vec<Record>>(format!("[({})]", s)).unwrap()
+        );
+        assert!(from_str::<Record>("name : \"a\" tags : [\"x\"]").is_err());
+
+        // the rest of the fields can be collected too
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Open {
+            name: String,
+            #[serde(flatten)]
+            rest: HashMap<String, Value>,
+        }
+        let open: Open = from_str("name : \"a\" x : 1 y : (z : true)").unwrap();
+        assert_eq!(Value::int(1), open.rest["x"]);
+        assert_eq!(Value::bool(true), open.rest["y"]["z"]);
+
+        let map: HashMap<String, u32> = from_str("(a : 1 b : 2)").unwrap();
+        assert_eq!(HashMap::from([("a".into(), 1), ("b".into(), 2)]), map);
+    }
+
+    #[test]
+    fn test_anchors() {
+        #[derive(Deserialize, Debug, PartialEq, Clone)]
+        struct Server {
+            host: String,
+            port: u16,
+        }
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            default: Server,
+            sites: Vec<Server>,
+            backup: Server,
+        }
+        let parse = |s| {
+            let mut de = Deserializer::from_str(s).with_anchors(true);
+            let config = Config::deserialize(&mut de)?;
+            de.end().map(|_| config)
+        };
+        let server = Server {
+            host: "a".into(),
+            port: 80,
+        };
+        let config = parse(
+            "default : &base (host : \"a\" port : 80)
+             sites : [*base /* comment */ *base]
+             backup : *base",
+        )
+        .unwrap();
+        assert_eq!(server, config.default);
+        assert_eq!(vec![server.clone(), server.clone()], config.sites);
+        assert_eq!(server, config.backup);
+
+        // anchors on scalars, and inside anchored values
+        let mut de =
+            Deserializer::from_str("[&one 1 &pair [*one &two 2] *pair *two]").with_anchors(true);
+        assert_eq!(
+            Value::Seq(vec![
+                Value::int(1),
+                Value::Seq(vec![Value::int(1), Value::int(2)]),
+                Value::Seq(vec![Value::int(1), Value::int(2)]),
+                Value::int(2),
+            ]),
+            Value::deserialize(&mut de).unwrap()
+        );
+
+        let err = parse("default : *base sites : [] backup : *base").unwrap_err();
+        assert_eq!((1, 10), (err.line(), err.col()));
+        assert_eq!(Some("undefined anchor `base`"), err.hint());
+        // an anchored value can't refer to itself
+        let mut de = Deserializer::from_str("[&a [*a]]").with_anchors(true);
+        assert!(Value::deserialize(&mut de).is_err());
+        // off by default
+        assert!(from_str::<Value>("[&a 1 *a]").is_err());
+        let options = DeOptions::new().with_anchors(true);
+        assert_eq!(
+            vec![1, 1],
+            from_str_with_options::<Vec<u8>>("[&a 1 *a]", &options).unwrap()
+        );
+
+        // each level doubles the size of the last, which has to fail fast
+        let mut s = "[&a0 [1 1]".to_string();
+        for i in 1..22 {
+            s.push_str(&format!(" &a{} [*a{} *a{}]", i, i - 1, i - 1));
+        }
+        s.push(']');
+        let time = std::time::Instant::now();
+        let err = from_str_with_options::<Value>(&s, &options).unwrap_err();
+        assert!(time.elapsed() < std::time::Duration::from_secs(10));
+        assert_eq!(&ErrorKind::ExpansionLimit(MIN_EXPANSION), err.kind());
+        assert!(err
+            .to_string()
+            .starts_with("error: anchors expand to more than 1048576 bytes at line: 1"));
+        // anchors in an expanded copy aren't defined again
+        let mut de = Deserializer::from_str("[&a [&b 1] *a *a *a]").with_anchors(true);
+        Value::deserialize(&mut de).unwrap();
+        assert_eq!(2, de.anchors.as_ref().unwrap().len());
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut de = Deserializer::from_str("[1 two]");
+        let start = de.save();
+        assert_eq!(
+            vec![Value::int(1), Value::from("two")],
+            Vec::<Value>::deserialize(&mut de).unwrap()
+        );
+        de.end().unwrap();
+        de.restore(start);
+        assert!(Vec::<u32>::deserialize(&mut de).is_err());
+
+        // the implicit wrapping is restored too
+        let mut de = Deserializer::from_str("a : 1");
+        let start = de.save();
+        assert!(u32::deserialize(&mut de).is_err());
+        de.restore(start);
+        let map: HashMap<String, u32> = Deserialize::deserialize(&mut de).unwrap();
+        assert_eq!(Some(&1), map.get("a"));
+        de.end().unwrap();
+
+        // cloning gives an independent deserializer
+        let mut de = Deserializer::from_str("[1] [2]");
+        let mut copy = de.clone();
+        assert_eq!(vec![1], Vec::<u8>::deserialize(&mut copy).unwrap());
+        assert_eq!(vec![1], Vec::<u8>::deserialize(&mut de).unwrap());
+        assert_eq!(de.remaining(), copy.remaining());
+
+        // anchors defined after the checkpoint are dropped on restoring it
+        let mut de = Deserializer::new(None, "[&a 1 *a] [&b 2]", None).with_anchors(true);
+        let start = de.save();
+        assert_eq!(vec![1, 1], Vec::<u8>::deserialize(&mut de).unwrap());
+        let middle = de.save();
+        assert_eq!(vec![2], Vec::<u8>::deserialize(&mut de).unwrap());
+        assert_eq!(2, de.anchors.as_ref().unwrap().len());
+        de.restore(middle);
+        let names: Vec<_> = de.anchors.as_ref().unwrap().iter().map(|a| a.0).collect();
+        assert_eq!(vec!["a"], names);
+        de.restore(start);
+        assert!(de.anchors.as_ref().unwrap().is_empty());
+        assert_eq!(vec![1, 1], Vec::<u8>::deserialize(&mut de).unwrap());
+    }
+
+    #[test]
+    fn test_addresses() {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            ip: IpAddr,
+            v4: Ipv4Addr,
+            v6: IpAddr,
+            listen: SocketAddr,
+        }
+        assert_eq!(
+            Config {
+                ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                v4: Ipv4Addr::LOCALHOST,
+                v6: IpAddr::V6(Ipv6Addr::LOCALHOST),
+                listen: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            },
+            from_str(r#"ip : "10.0.0.1" v4 : "127.0.0.1" v6 : "::1" listen : "127.0.0.1:8080""#)
+                .unwrap()
+        );
+        assert_eq!(
+            vec![SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 1], 443))],
+            from_str::<Vec<SocketAddr>>(r#"["[::1]:443"]"#).unwrap()
+        );
+
+        let err = from_str::<Vec<Ipv4Addr>>("[127.0.0.1]").unwrap_err();
+        assert_eq!(Some("expected a string, found `127.0.0.1`"), err.hint());
+        let err = from_str::<Ipv4Addr>(r#""127.0.0.256""#).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Custom(_)));
+    }
+
+    #[test]
+    fn test_paths() {
+        use std::{ffi::OsString, path::PathBuf};
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            path: PathBuf,
+            name: OsString,
+            paths: Vec<PathBuf>,
+        }
+        assert_eq!(
+            Config {
+                path: PathBuf::from("/etc/app.conf"),
+                name: OsString::from("λ app"),
+                paths: vec![PathBuf::from("a/b"), PathBuf::from("c")],
+            },
+            from_str(r#"path : "/etc/app.conf" name : "λ app" paths : ["a/b" "c"]"#).unwrap()
+        );
+        assert_eq!(OsString::from("x"), from_str::<OsString>(r#""x""#).unwrap());
+        // the platform's variant can still be written out
+        #[cfg(unix)]
+        assert_eq!(
+            OsString::from("hi"),
+            from_str::<OsString>("Unix : [104 105]").unwrap()
+        );
+        assert!(from_str::<Vec<OsString>>("[1]").is_err());
+
+        // only serde's own OsString enum is read from a plain string
+        mod user {
+            #[derive(serde::Deserialize, Debug, PartialEq)]
+            pub enum OsString {
+                Unix,
+                Other(String),
+            }
+        }
+        assert_eq!(
+            user::OsString::Unix,
+            from_str::<user::OsString>(r#""Unix""#).unwrap()
+        );
+        assert!(from_str::<user::OsString>(r#""λ app""#).is_err());
+    }
+
+    #[test]
+    fn test_from_prefix() {
+        let input = "[1 2 3] // done\n%% not jacl";
+        let (seq, consumed) = from_prefix::<Vec<u8>>(input).unwrap();
+        assert_eq!(vec![1, 2, 3], seq);
+        assert_eq!("%% not jacl", &input[consumed..]);
+        // from_str rejects the trailing text
+        assert!(from_str::<Vec<u8>>(input).is_err());
+
+        let mut de = Deserializer::new(None, r#""λ" rest"#, None);
+        assert_eq!("λ", String::deserialize(&mut de).unwrap());
+        assert_eq!("rest", de.remaining());
+    }
+
+    #[test]
+    fn test_stream() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Record {
+            id: u32,
+            tag: String,
+        }
+        let input = r#"
+            // records
+            (id : 1 tag : "a")
+            /* skipped */ (id : 2 tag : "b") # trailing
+            (id : 3 tag : "c")
+            // the end
+        "#;
+        let records: Vec<Record> = StreamDeserializer::new(input)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let tags: Vec<_> = records.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(vec!["a", "b", "c"], tags);
+        assert_eq!(3, records[2].id);
+
+        assert_eq!(0, StreamDeserializer::<Record>::new(" // nothing ").count());
+
+        // errors point into the whole document, and end the stream
+        let mut stream = StreamDeserializer::<Record>::new("(id : 1 tag : \"a\")\n%");
+        assert!(stream.next().unwrap().is_ok());
+        let err = stream.next().unwrap().unwrap_err();
+        assert_eq!((2, 0), (err.line, err.col));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_enum() {
+        #[derive(Deserialize, Serialize, PartialEq, Debug)]
+        enum Shape {
+            Empty,
+            Circle(f64),
+            Line(i32, i32),
+            Rect { w: u32, h: u32 },
+        }
+
+        #[derive(Deserialize, Serialize, PartialEq, Debug)]
+        struct Test {
+            shape: Shape,
+            shapes: Vec<Shape>,
+            nested: Option<Shape>,
+        }
+
+        let shapes = vec![
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Line(-1, 2),
+            Shape::Rect { w: 3, h: 4 },
+        ];
+        for shape in shapes {
+            let s = to_string(&shape).unwrap();
+            assert_eq!(shape, from_str::<Shape>(&s).unwrap(), "{}", s);
+            let test = Test {
+                shape: Shape::Empty,
+                shapes: vec![Shape::Circle(0.5), shape],
+                nested: Some(Shape::Line(0, 0)),
+            };
+            let s = to_string(&test).unwrap();
+            assert_eq!(test, from_str::<Test>(&s).unwrap(), "{}", s);
+        }
+
+        assert_eq!(
+            "Rect:(w:3 h:4)",
+            to_string(&Shape::Rect { w: 3, h: 4 }).unwrap()
+        );
+        let test: Test = from_str(
+            "shape : Empty
+             shapes : [Circle : 2 Line : [1 2] Rect : (w : 1 h : 2)]
+             nested : Empty",
+        )
+        .unwrap();
+        assert_eq!(Shape::Rect { w: 1, h: 2 }, test.shapes[2]);
+        assert_eq!(Some(Shape::Empty), from_str("Empty").unwrap());
+        assert!(from_str::<Shape>("Circle").is_err());
+        assert!(from_str::<Shape>("Hexagon").is_err());
+        assert!(from_str::<Shape>("Circle [1]").is_err());
+    }
+
+    #[test]
+    fn test_string_variants() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        #[serde(rename_all = "lowercase")]
+        enum Level {
+            Debug,
+            Warn,
+        }
+        #[derive(Deserialize, PartialEq, Debug)]
+        enum Shape {
+            Empty,
+            Circle(f64),
+        }
+
+        assert_eq!(Level::Debug, from_str::<Level>(r#""debug""#).unwrap());
+        assert_eq!(Level::Debug, from_str::<Level>("debug").unwrap());
+        assert_eq!(
+            vec![Level::Warn, Level::Debug],
+            from_str::<Vec<Level>>(r#"["warn" debug]"#).unwrap()
+        );
+        assert_eq!(
+            HashMap::from([("level".to_string(), Level::Warn)]),
+            from_str::<HashMap<String, Level>>(r#"{"level" : "\u0077arn"}"#).unwrap()
+        );
+        assert!(from_str::<Level>(r#""trace""#).is_err());
+        // only unit variants can be written as strings
+        assert_eq!(Shape::Empty, from_str::<Shape>(r#""Empty""#).unwrap());
+        let err = from_str::<Vec<Shape>>(r#"["Empty" "Circle"]"#).unwrap_err();
+        assert_eq!((1, 9), (err.line(), err.col()));
+    }
+
+    #[test]
+    fn test_tuple() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            pair: (u8, String),
+            after: u8,
+        }
+        let test: Test = from_str(r#"pair : [1 "a"] after : 2"#).unwrap();
+        assert_eq!((1, "a".to_string()), test.pair);
+        assert_eq!(2, test.after);
+        assert!(from_str::<Test>(r#"pair : [1 "a" 3] after : 2"#).is_err());
+        assert_eq!(
+            vec![(1, 2), (3, 4)],
+            from_str::<Vec<(u8, u8)>>("[[1 2] [3 4]]").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_untagged() {
+        #[derive(Deserialize, Serialize, PartialEq, Debug)]
+        #[serde(untagged)]
+        enum Shape {
+            Circle { radius: f64 },
+            Rect { w: u32, h: u32 },
+            Label(String),
+            Count(i64),
+        }
+
+        let shapes: Vec<Shape> = from_str(r#"[(radius : 1.5) (w : 2 h : 3) "hi" bare 7]"#).unwrap();
+        assert_eq!(
+            vec![
+                Shape::Circle { radius: 1.5 },
+                Shape::Rect { w: 2, h: 3 },
+                Shape::Label("hi".to_string()),
+                Shape::Label("bare".to_string()),
+                Shape::Count(7),
+            ],
+            shapes
+        );
+        assert_eq!(
+            shapes,
+            from_str::<Vec<Shape>>(&to_string(&shapes).unwrap()).unwrap()
+        );
+        assert!(from_str::<Vec<Shape>>("[(w : 2)]").is_err());
+
+        #[derive(Deserialize, Serialize, PartialEq, Debug)]
+        #[serde(tag = "type")]
+        enum Event {
+            Click { x: i32, y: i32 },
+            Key { code: u32 },
+        }
+
+        let events: Vec<Event> =
+            from_str(r#"[(type : Click x : 1 y : -2) {"type" : "Key" "code" : 13}]"#).unwrap();
+        assert_eq!(
+            vec![Event::Click { x: 1, y: -2 }, Event::Key { code: 13 }],
+            events
+        );
+        assert_eq!(
+            events,
+            from_str::<Vec<Event>>(&to_string(&events).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tuple_len() {
+        type Triple = (i32, i32, i32);
+        assert_eq!((1, 2, 3), from_str::<Triple>("[1 2 3]").unwrap());
+        let err = from_str::<Triple>("[1 2]").unwrap_err();
+        assert_eq!((1, 4), (err.line(), err.col()));
+        assert_eq!(Some("expected 3 elements, found 2"), err.hint());
+        let err = from_str::<Triple>("[1 2 3 4]").unwrap_err();
+        assert_eq!((1, 7), (err.line(), err.col()));
+        assert_eq!(Some("expected 3 elements, found more"), err.hint());
+
+        assert_eq!([1, 2, 3, 4], from_str::<[u8; 4]>("[1 2 3 4]").unwrap());
+        let err = from_str::<[u8; 4]>("[]").unwrap_err();
+        assert_eq!(Some("expected 4 elements, found 0"), err.hint());
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Pair(u8, u8);
+        let err = from_str::<Pair>("[1 2 3]").unwrap_err();
+        assert_eq!(Some("expected 2 elements, found more"), err.hint());
+    }
+
+    #[test]
+    fn test_byte_arrays() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Key {
+            id: [u8; 4],
+        }
+        let key = Key {
+            id: [0xde, 0xad, 0xbe, 0xef],
+        };
+        assert_eq!(key, from_str(r#"id : "deadbeef""#).unwrap());
+        assert_eq!(key, from_str(r#"id : "DEADBEEF""#).unwrap());
+        assert_eq!(key, from_str("id : [222 173 190 239]").unwrap());
+        assert_eq!([0u8; 0], from_str::<[u8; 0]>("\"\"").unwrap());
+
+        let err = from_str::<Key>(r#"id : "deadbe""#).unwrap_err();
+        assert_eq!((1, 5), (err.line(), err.col()));
+        assert_eq!(Some("expected 4 bytes, found 3"), err.hint());
+        let err = from_str::<Key>(r#"id : "deadbeef00""#).unwrap_err();
+        assert_eq!(Some("expected 4 bytes, found 5"), err.hint());
+        let err = from_str::<Key>(r#"id : "deadbeeg""#).unwrap_err();
+        assert_eq!(Some("expected a string of hex digit pairs"), err.hint());
+        let err = from_str::<Key>(r#"id : "deadbee""#).unwrap_err();
+        assert_eq!(Some("expected a string of hex digit pairs"), err.hint());
+        let err = from_str::<Key>("id : [222 173 190]").unwrap_err();
+        assert_eq!(Some("expected 4 elements, found 3"), err.hint());
+        let err = from_str::<Key>("id : [222 173 190 239 0]").unwrap_err();
+        assert_eq!(Some("expected 4 elements, found more"), err.hint());
+        assert!(from_str::<Key>("id : [222 173 190 256]").is_err());
+
+        // only tuples of bytes are read from hex strings
+        let err = from_str::<[u32; 4]>(r#""deadbeef""#).unwrap_err();
+        assert_eq!(Some("expected `[`, found a string"), err.hint());
+        assert!(from_str::<(i64, f64)>(r#""0102""#).is_err());
+        assert!(from_str::<[u32; 2]>(r#""xyz""#).is_err());
+        let err = from_str::<Vec<(String, u8)>>(r#"["ab01"]"#).unwrap_err();
+        assert_eq!((1, 1), (err.line(), err.col()));
+        assert_eq!(Some("expected `[`, found a string"), err.hint());
+    }
+
+    #[test]
+    fn test_option_null_prefix() {
+        let expected = vec![
+            Some(Value::string("nullable")),
+            None,
+            Some(Value::string("null_")),
+        ];
+        assert_eq!(
+            expected,
+            from_str::<Vec<Option<Value>>>("[nullable null null_]").unwrap()
+        );
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            a: Option<Value>,
+            b: Option<u32>,
+        }
+        assert_eq!(
+            Test {
+                a: Some(Value::string("nullish")),
+                b: None
+            },
+            from_str::<Test>("(a : nullish b : null/* */)").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_equals_separator() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            name: String,
+            port: u16,
+            tags: HashMap<String, bool>,
+        }
+
+        let input = r#"name = "x" port : 8080 tags = { "a" = true "b" : false }"#;
+        let mut de = Deserializer::from_str(input).with_equals_separator(true);
+        let test = Test::deserialize(&mut de).unwrap();
+        assert!(de.input.is_empty());
+        assert_eq!(
+            Test {
+                name: "x".to_string(),
+                port: 8080,
+                tags: HashMap::from([("a".to_string(), true), ("b".to_string(), false)]),
+            },
+            test
+        );
+
+        let mut de = Deserializer::from_str(r#""a" = 1 "b" = 2"#).with_equals_separator(true);
+        let map = HashMap::<String, u32>::deserialize(&mut de).unwrap();
+        assert_eq!(
+            HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]),
+            map
+        );
+
+        // off by default
+        let err = from_str::<Test>(input).unwrap_err();
+        assert_eq!(Some("expected `:`, found `=`"), err.hint());
+        let mut de = Deserializer::from_str("(port 1)").with_equals_separator(true);
+        let err = Test::deserialize(&mut de).unwrap_err();
+        assert_eq!(Some("expected `:` or `=`, found `1`"), err.hint());
+    }
+
+    #[test]
+    fn test_strict_commas() {
+        fn strict<T: DeserializeOwned>(s: &str) -> Result<T, JaclDeError> {
+            let mut de = Deserializer::from_str(s).with_strict_commas(true);
+            let t = T::deserialize(&mut de)?;
+            de.skip_non_tokens()?;
+            assert!(de.input.is_empty());
+            Ok(t)
+        }
+
+        // lenient by default
+        assert_eq!(vec![1, 2], from_str::<Vec<u8>>("[1,,2]").unwrap());
+        assert_eq!(vec![1, 2], from_str::<Vec<u8>>("[1, 2,]").unwrap());
+
+        assert_eq!(vec![1, 2], strict::<Vec<u8>>("[1, 2]").unwrap());
+        assert_eq!(vec![1, 2], strict::<Vec<u8>>("[1 2]").unwrap());
+        assert_eq!(vec![1, 2, 3], strict::<Vec<u8>>("1, 2 /* */, 3").unwrap());
+        assert_eq!(Vec::<u8>::new(), strict::<Vec<u8>>("[]").unwrap());
+
+        let err = strict::<Vec<u8>>("[1,,2]").unwrap_err();
+        assert_eq!((1, 3), (err.line(), err.col()));
+        assert_eq!(Some("expected a value, found `,`"), err.hint());
+        let err = strict::<Vec<u8>>("[1, 2,]").unwrap_err();
+        assert_eq!((1, 5), (err.line(), err.col()));
+        assert_eq!(Some("expected `]`, found `,`"), err.hint());
+        let err = strict::<Vec<u8>>("[,1]").unwrap_err();
+        assert_eq!((1, 1), (err.line(), err.col()));
+        let err = strict::<Vec<u8>>("1, 2,").unwrap_err();
+        assert_eq!((1, 4), (err.line(), err.col()));
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            a: u8,
+            b: HashMap<String, u8>,
+        }
+        let expected = Test {
+            a: 1,
+            b: HashMap::from([("x".to_string(), 2), ("y".to_string(), 3)]),
+        };
+        assert_eq!(
+            expected,
+            strict::<Test>(r#"(a : 1, b : {"x" : 2, "y" : 3})"#).unwrap()
+        );
+        let err = strict::<Test>(r#"(a : 1, b : {"x" : 2, "y" : 3,})"#).unwrap_err();
+        assert_eq!(Some("expected `}`, found `,`"), err.hint());
+        let err = strict::<Test>(r#"(a : 1,, b : {})"#).unwrap_err();
+        assert_eq!(Some("expected a field name, found `,`"), err.hint());
+        let err = strict::<Test>(r#"(a, : 1 b : {})"#).unwrap_err();
+        assert_eq!(Some("expected `:`, found `,`"), err.hint());
+
+        // a comma between a key and its colon is a typo, not a separator
+        let lenient = from_str::<HashMap<String, u8>>(r#"{"a", : 1}"#).unwrap();
+        assert_eq!(HashMap::from([("a".to_string(), 1)]), lenient);
+        let err = strict::<HashMap<String, u8>>("{\"a\" : 1\n \"b\" , : 2}").unwrap_err();
+        assert_eq!((2, 5), (err.line(), err.col()));
+        assert_eq!(Some("expected `:`, found `,`"), err.hint());
+        let mut de = Deserializer::from_str(r#"{"a", = 1}"#)
+            .with_strict_commas(true)
+            .with_equals_separator(true);
+        let err = HashMap::<String, u8>::deserialize(&mut de).unwrap_err();
+        assert_eq!((1, 4), (err.line(), err.col()));
+        assert_eq!(Some("expected `:` or `=`, found `,`"), err.hint());
+    }
+
+    #[test]
+    fn test_deny_keys() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            a: u8,
+            b: Option<HashMap<String, u8>>,
+        }
+
+        fn parse<T: DeserializeOwned>(s: &str, unknown: bool, dup: bool) -> Result<T, JaclDeError> {
+            let mut de = Deserializer::from_str(s)
+                .with_deny_unknown_fields(unknown)
+                .with_deny_duplicate_keys(dup);
+            T::deserialize(&mut de)
+        }
+
+        // lenient by default
+        let unknown = "(a : 1\nc : 2)";
+        assert_eq!(
+            Test { a: 1, b: None },
+            parse::<Test>(unknown, false, false).unwrap()
+        );
+        let err = parse::<Test>(unknown, true, false).unwrap_err();
+        assert_eq!((2, 0), (err.line(), err.col()));
+        assert_eq!(
+            Some("unknown field `c`, expected one of `a`, `b`"),
+            err.hint()
+        );
+        // quoted field names are checked too
+        let err = parse::<Test>(r#"(a : 1 "c" : 2)"#, true, false).unwrap_err();
+        assert_eq!((1, 7), (err.line(), err.col()));
+        // any field goes in a Value
+        assert!(parse::<Value>(unknown, true, true).is_ok());
+
+        // derived impls reject duplicate fields, but without a location
+        let dup = "(a : 1 a : 2)";
+        let err = parse::<Test>(dup, false, false).unwrap_err();
+        assert_eq!(0, err.line());
+        let err = parse::<Test>(dup, false, true).unwrap_err();
+        assert_eq!((1, 7), (err.line(), err.col()));
+        assert_eq!(Some("duplicate key `a`"), err.hint());
+
+        let dup = r#"(a : 1 b : {"x" : 1 "y" : 2 "x" : 3})"#;
+        assert!(parse::<Test>(dup, true, false).is_ok());
+        let err = parse::<Test>(dup, false, true).unwrap_err();
+        assert_eq!((1, 28), (err.line(), err.col()));
+        assert_eq!(Some("duplicate key `x`"), err.hint());
+        // the same key in different containers isn't a duplicate
+        assert!(parse::<Vec<Test>>("[(a : 1) (a : 2)]", true, true).is_ok());
+    }
+
+    #[test]
+    fn test_int_range() {
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Test {
+            a: u8,
+            b: i8,
+            c: u32,
+        }
+
+        let err = from_str::<Test>("(a : 300 b : 0 c : 0)").unwrap_err();
+        assert_eq!((1, 5), (err.line(), err.col()));
+        assert_eq!(Some("value 300 out of range for u8"), err.hint());
+        let err = from_str::<Test>("(a : 0\nb : -129 c : 0)").unwrap_err();
+        assert_eq!((2, 4), (err.line(), err.col()));
+        assert_eq!(Some("value -129 out of range for i8"), err.hint());
+        let err = from_str::<Test>("(a : 0 b : 0 c : -1)").unwrap_err();
+        assert_eq!(Some("value -1 out of range for u32"), err.hint());
+        let err = from_str::<Test>("(a : 0 b : 0 c : 0x1_0000_0000)").unwrap_err();
+        assert_eq!(Some("value 0x1_0000_0000 out of range for u32"), err.hint());
+        let err = from_str::<Test>("(a : 0 b : 0 c : 99999999999999999999999999999999999999999)")
+            .unwrap_err();
+        assert!(err.hint().unwrap().ends_with("out of range for u32"));
+        let err = from_str::<Test>("(a : x b : 0 c : 0)").unwrap_err();
+        assert_eq!(Some("expected an integer, found `x`"), err.hint());
+    }
+
+    #[test]
+    fn test_borrowed_keys() {
+        let input = r#"{"alpha" : 1 "beta" : -2}"#;
+        let (map, _) = from_str_remaining::<HashMap<&str, i64>>(input).unwrap();
+        assert_eq!(HashMap::from([("alpha", 1), ("beta", -2)]), map);
+        // the keys point into the input rather than being copied
+        for key in map.keys() {
+            let offset = key.as_ptr() as usize - input.as_ptr() as usize;
+            assert_eq!(*key, &input[offset..(offset + key.len())]);
+        }
+        // escaped keys can't be borrowed
+        assert!(from_str_remaining::<HashMap<&str, i64>>(r#"{"a\n" : 1}"#).is_err());
+    }
+
     #[test]
     fn test_option() {
         assert_eq!(Some(0), from_str::<Option<u32>>("0").unwrap());
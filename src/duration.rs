@@ -0,0 +1,164 @@
+//! Reading [`Duration`]s from strings like `"30s"` or `"1h30m"`.
+//!
+//! ```
+//! use serde::Deserialize;
+//! use std::time::Duration;
+//!
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     #[serde(deserialize_with = "serde_jacl::duration::deserialize")]
+//!     timeout: Duration,
+//! }
+//!
+//! let config: Config = serde_jacl::de::from_str(r#"timeout : "1m30s""#).unwrap();
+//! assert_eq!(Duration::from_secs(90), config.timeout);
+//! ```
+
+use serde::{de, Deserialize, Deserializer};
+use std::{error::Error, fmt, time::Duration};
+
+/// Why a duration string couldn't be parsed.
+#[derive(Debug, PartialEq, Clone)]
+pub enum DurationError {
+    /// The string is empty.
+    Empty,
+    /// A number isn't followed by a unit, e.g. the `5` in `"1h5"`.
+    MissingUnit(String),
+    /// The unit isn't one of `ns`, `us`, `ms`, `s`, `m`, `h` or `d`.
+    UnknownUnit(String),
+    /// The string doesn't start with a number, e.g. `"h"` or `"-1s"`.
+    ExpectedNumber(String),
+    /// The duration doesn't fit in a [`Duration`].
+    Overflow,
+}
+
+impl fmt::Display for DurationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DurationError::Empty => write!(f, "empty duration"),
+            DurationError::MissingUnit(num) => write!(f, "missing a unit after `{}`", num),
+            DurationError::UnknownUnit(unit) => write!(
+                f,
+                "unknown duration unit `{}`, expected one of ns, us, ms, s, m, h, d",
+                unit
+            ),
+            DurationError::ExpectedNumber(rest) => write!(f, "expected a number at `{}`", rest),
+            DurationError::Overflow => write!(f, "duration is too long"),
+        }
+    }
+}
+
+impl Error for DurationError {}
+
+/// Parses a duration made of one or more whole numbers, each followed by a
+/// unit: `ns`, `us`, `ms`, `s`, `m`, `h` or `d`. The parts are added up, so
+/// `"1h30m"` is 90 minutes.
+pub fn parse(s: &str) -> Result<Duration, DurationError> {
+    if s.is_empty() {
+        return Err(DurationError::Empty);
+    }
+    let mut total = Duration::from_secs(0);
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(DurationError::ExpectedNumber(rest.to_string()));
+        }
+        let (num, after) = rest.split_at(digits);
+        let units = after
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(units);
+        let n: u64 = num.parse().map_err(|_| DurationError::Overflow)?;
+        let part = match unit {
+            "ns" => Duration::from_nanos(n),
+            "us" => Duration::from_micros(n),
+            "ms" => Duration::from_millis(n),
+            "s" => Duration::from_secs(n),
+            "m" => secs(n, 60)?,
+            "h" => secs(n, 60 * 60)?,
+            "d" => secs(n, 24 * 60 * 60)?,
+            "" => return Err(DurationError::MissingUnit(num.to_string())),
+            _ => return Err(DurationError::UnknownUnit(unit.to_string())),
+        };
+        total = total.checked_add(part).ok_or(DurationError::Overflow)?;
+        rest = after;
+    }
+    Ok(total)
+}
+
+fn secs(n: u64, scale: u64) -> Result<Duration, DurationError> {
+    n.checked_mul(scale)
+        .map(Duration::from_secs)
+        .ok_or(DurationError::Overflow)
+}
+
+/// Deserializes a [`Duration`] from a string accepted by [`parse`], for use
+/// with `#[serde(deserialize_with = "serde_jacl::duration::deserialize")]`.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    parse(&s).map_err(|err| de::Error::custom(format!("invalid duration \"{}\": {}", s, err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::{from_str, ErrorKind};
+
+    #[test]
+    fn test_single_unit() {
+        assert_eq!(Ok(Duration::from_nanos(5)), parse("5ns"));
+        assert_eq!(Ok(Duration::from_micros(5)), parse("5us"));
+        assert_eq!(Ok(Duration::from_millis(250)), parse("250ms"));
+        assert_eq!(Ok(Duration::from_secs(30)), parse("30s"));
+        assert_eq!(Ok(Duration::from_secs(600)), parse("10m"));
+        assert_eq!(Ok(Duration::from_secs(7200)), parse("2h"));
+        assert_eq!(Ok(Duration::from_secs(86400)), parse("1d"));
+    }
+
+    #[test]
+    fn test_compound() {
+        assert_eq!(Ok(Duration::from_secs(5400)), parse("1h30m"));
+        assert_eq!(
+            Ok(Duration::from_secs(86400 + 1) + Duration::from_millis(500)),
+            parse("1d1s500ms")
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(Err(DurationError::UnknownUnit("x".into())), parse("5x"));
+        assert_eq!(
+            Err(DurationError::UnknownUnit("sec".into())),
+            parse("1h5sec")
+        );
+        assert_eq!(Err(DurationError::MissingUnit("5".into())), parse("1h5"));
+        assert_eq!(Err(DurationError::ExpectedNumber("h".into())), parse("h"));
+        assert_eq!(Err(DurationError::Empty), parse(""));
+        assert_eq!(Err(DurationError::Overflow), parse("99999999999999999999d"));
+    }
+
+    #[test]
+    fn test_deserialize() {
+        #[derive(Deserialize, Debug)]
+        struct Config {
+            #[serde(deserialize_with = "deserialize")]
+            timeout: Duration,
+        }
+        let config = from_str::<Config>(r#"timeout : "1h30m""#).unwrap();
+        assert_eq!(Duration::from_secs(5400), config.timeout);
+        let err = from_str::<Config>(r#"timeout : "5x""#).unwrap_err();
+        assert_eq!(
+            &ErrorKind::Custom(
+                "invalid duration \"5x\": unknown duration unit `x`, expected one of ns, us, ms, s, m, h, d"
+                    .into()
+            ),
+            err.kind()
+        );
+    }
+}
@@ -1,4 +1,16 @@
+#[cfg(feature = "bigint")]
+pub mod bigint;
 pub mod de;
+pub mod duration;
+#[macro_use]
+mod macros;
 mod parsing;
 pub mod ser;
+pub mod spanned;
 pub mod structs;
+pub mod time;
+
+#[cfg(feature = "bigint")]
+pub use bigint::JaclBigInt;
+pub use de::JaclDeError;
+pub use ser::JaclSerError;
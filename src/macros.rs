@@ -0,0 +1,137 @@
+/// Builds a [`Value`](crate::structs::Value) from JSON-like syntax.
+///
+/// ```
+/// # use serde_jacl::jacl;
+/// let count = 3i64;
+/// let value = jacl!({
+///     "name": "jacl",
+///     "count": count,
+///     "flags": [true, null, -1.5],
+/// });
+/// ```
+#[macro_export]
+macro_rules! jacl {
+    // sequences: collect comma separated elements, each of which may be several tokens
+    (@seq [$($elems:expr,)*] ()) => {
+        vec![$($elems,)*]
+    };
+    (@seq [$($elems:expr,)*] ($($elem:tt)+)) => {
+        vec![$($elems,)* $crate::jacl!($($elem)+),]
+    };
+    (@seq [$($elems:expr,)*] ($($elem:tt)+) , $($rest:tt)*) => {
+        $crate::jacl!(@seq [$($elems,)* $crate::jacl!($($elem)+),] () $($rest)*)
+    };
+    (@seq [$($elems:expr,)*] ($($elem:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::jacl!(@seq [$($elems,)*] ($($elem)* $next) $($rest)*)
+    };
+
+    // maps: a key is a string literal or a parenthesized expression, its value runs until the next comma
+    (@map $map:ident) => {};
+    (@map $map:ident $key:tt : $($rest:tt)*) => {
+        $crate::jacl!(@entry $map $key () $($rest)*)
+    };
+    (@entry $map:ident $key:tt ($($value:tt)+)) => {
+        $map.insert(::std::string::String::from($key), $crate::jacl!($($value)+));
+    };
+    (@entry $map:ident $key:tt ($($value:tt)+) , $($rest:tt)*) => {
+        $map.insert(::std::string::String::from($key), $crate::jacl!($($value)+));
+        $crate::jacl!(@map $map $($rest)*)
+    };
+    (@entry $map:ident $key:tt ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::jacl!(@entry $map $key ($($value)* $next) $($rest)*)
+    };
+
+    (null) => {
+        $crate::structs::Value::null()
+    };
+    (true) => {
+        $crate::structs::Value::bool(true)
+    };
+    (false) => {
+        $crate::structs::Value::bool(false)
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::structs::Value::Seq($crate::jacl!(@seq [] () $($tt)*))
+    };
+    ({ $($tt:tt)* }) => {{
+        #[allow(unused_mut)]
+        let mut map = ::std::collections::HashMap::new();
+        $crate::jacl!(@map map $($tt)*);
+        $crate::structs::Value::Map(map)
+    }};
+    ($other:expr) => {
+        $crate::structs::Value::from($other)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::Value;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(Value::null(), jacl!(null));
+        assert_eq!(Value::bool(true), jacl!(true));
+        assert_eq!(Value::bool(false), jacl!(false));
+        assert_eq!(Value::int(1), jacl!(1));
+        assert_eq!(Value::int(-1), jacl!(-1));
+        assert_eq!(Value::flt(1.5), jacl!(1.5));
+        assert_eq!(Value::string("a"), jacl!("a"));
+    }
+
+    #[test]
+    fn test_seq() {
+        assert_eq!(Value::Seq(vec![]), jacl!([]));
+        let expected = Value::Seq(vec![
+            Value::int(-1),
+            Value::Seq(vec![Value::null()]),
+            Value::string("a"),
+        ]);
+        assert_eq!(expected, jacl!([-1, [null], "a"]));
+        assert_eq!(expected, jacl!([-1, [null,], "a",]));
+    }
+
+    #[test]
+    fn test_map() {
+        assert_eq!(Value::Map(HashMap::new()), jacl!({}));
+        let expected = Value::Map(HashMap::from([
+            ("a".into(), Value::int(1)),
+            (
+                "b".into(),
+                Value::Seq(vec![Value::bool(true), Value::null()]),
+            ),
+            (
+                "c".into(),
+                Value::Map(HashMap::from([("d".into(), Value::flt(-2.5))])),
+            ),
+        ]));
+        assert_eq!(
+            expected,
+            jacl!({ "a": 1, "b": [true, null], "c": { "d": -2.5 } })
+        );
+        assert_eq!(
+            expected,
+            jacl!({ "a": 1, "b": [true, null,], "c": { "d": -2.5, }, })
+        );
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let my_var: i64 = 7;
+        let name = String::from("x");
+        let inner = jacl!([1]);
+        let expected = Value::Map(HashMap::from([
+            ("x".into(), Value::int(7)),
+            ("y".into(), Value::int(8)),
+            (
+                "z".into(),
+                Value::Seq(vec![Value::Seq(vec![Value::int(1)])]),
+            ),
+        ]));
+        assert_eq!(
+            expected,
+            jacl!({ (name): my_var, "y": my_var + 1, "z": [inner] })
+        );
+    }
+}
@@ -13,14 +13,29 @@ use nom::{
 const ALPHA: &str = "qwertyuiopasdfghjklzxcvbnmQWERTYUIOPASDFGHJKLZXCVBNM_";
 const ALPHANUM: &str = "qwertyuiopasdfghjklzxcvbnmQWERTYUIOPASDFGHJKLZXCVBNM_1234567890";
 
-pub fn delimiter<'a>(input: &'a str) -> IResult<&'a str, char> {
-    return one_of(":(){}[]")(input);
+const DELIMITERS: &str = ":(){}[]";
+
+pub fn delimiter(input: &str) -> IResult<&str, char> {
+    return one_of(DELIMITERS)(input);
+}
+
+pub fn is_delimiter(c: char) -> bool {
+    DELIMITERS.contains(c)
 }
 
-pub fn identifier<'a>(input: &'a str) -> IResult<&'a str, &'a str> {
+pub fn is_identifier_char(c: char) -> bool {
+    ALPHANUM.contains(c)
+}
+
+pub fn identifier(input: &str) -> IResult<&str, &str> {
     recognize(pair(many1(one_of(ALPHA)), many0(one_of(ALPHANUM))))(input)
 }
 
-pub fn whitespace<'a>(input: &'a str) -> IResult<&'a str, ()> {
+pub fn whitespace(input: &str) -> IResult<&str, ()> {
     return value((), many1(one_of(" ,\r\n\t")))(input);
 }
+
+// whitespace without commas, for when commas are parsed as separators
+pub fn blank(input: &str) -> IResult<&str, ()> {
+    value((), many1(one_of(" \r\n\t")))(input)
+}
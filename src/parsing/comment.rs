@@ -1,15 +1,52 @@
 use nom::{
-    bytes::complete::{tag, take_until},
-    combinator::value,
-    error::ParseError,
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{opt, value},
+    error::{ErrorKind, ParseError},
     sequence::tuple,
-    IResult,
+    Err, IResult,
 };
 
+// multiline comments nest, so a block that contains comments can itself be commented out
 pub fn multiline_comment<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, (), E> {
-    value((), tuple((tag("/*"), take_until("*/"), tag("*/"))))(i)
+    let (mut rest, _) = tag("/*")(i)?;
+    let mut depth = 1;
+    while depth > 0 {
+        if rest.starts_with("/*") {
+            depth += 1;
+            rest = &rest[2..];
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            rest = &rest[2..];
+        } else if let Some(c) = rest.chars().next() {
+            rest = &rest[c.len_utf8()..];
+        } else {
+            return Err(Err::Error(E::from_error_kind(i, ErrorKind::TakeUntil)));
+        }
+    }
+    Ok((rest, ()))
 }
 
+// the newline is optional so a comment can end the input
 pub fn eol_comment<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, (), E> {
-    value((), tuple((tag("//"), take_until("\n"), tag("\n"))))(i)
+    value((), tuple((tag("//"), line_rest, opt(newline))))(i)
+}
+
+pub fn hash_comment<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, (), E> {
+    value((), tuple((tag("#"), line_rest, opt(newline))))(i)
+}
+
+// everything up to a `\n` or `\r\n` line ending
+fn line_rest<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    let end = i.find('\n').unwrap_or(i.len());
+    let end = if i[..end].ends_with('\r') {
+        end - 1
+    } else {
+        end
+    };
+    Ok((&i[end..], &i[..end]))
+}
+
+fn newline<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    alt((tag("\r\n"), tag("\n")))(i)
 }
@@ -1,9 +1,9 @@
 use nom::{
     branch::alt,
-    bytes::complete::tag,
+    bytes::complete::{tag, tag_no_case, take_while1},
     character::complete,
     character::complete::one_of,
-    combinator::{map_res, opt, recognize, value},
+    combinator::{map_res, not, opt, recognize, value},
     multi::{many0, many1},
     sequence::{pair, preceded, terminated, tuple},
     IResult,
@@ -12,47 +12,146 @@ use nom::{
 use num::{Float, Integer};
 use std::str::FromStr;
 
-pub fn integer<'a, T: Integer + FromStr>(input: &'a str) -> IResult<&'a str, T> {
+pub fn integer<T: Integer + FromStr>(input: &str) -> IResult<&str, T> {
+    // a radix integer that doesn't fit in T mustn't fall back to reading its
+    // leading 0 as a decimal
+    let unsigned = input.strip_prefix(&['-', '+'][..]).unwrap_or(input);
+    if ["0x", "0o", "0b"]
+        .iter()
+        .any(|prefix| unsigned.starts_with(prefix))
+    {
+        return radix_integer(input);
+    }
+    decimal_integer(input)
+}
+
+fn decimal_integer<T: Integer + FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(decimal_digits, |out: &str| {
+        let out = out.strip_prefix('+').unwrap_or(out);
+        T::from_str(&str::replace(out, "_", ""))
+    })(input)
+}
+
+/// The text of a decimal integer, however large. A leading `+` is allowed
+/// and means the same as no sign.
+pub fn decimal_digits(input: &str) -> IResult<&str, &str> {
+    recognize(pair(opt(one_of("+-")), digits))(input)
+}
+
+// unsigned decimal digits, with `_` allowed after each digit
+fn digits(input: &str) -> IResult<&str, &str> {
+    recognize(many1(terminated(
+        one_of("0123456789"),
+        many0(complete::char('_')),
+    )))(input)
+}
+
+// `e` or `E`, an optional sign, and the digits of the power
+fn exponent(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((one_of("eE"), opt(one_of("+-")), digits)))(input)
+}
+
+// 0xFF, 0o755 and 0b1010. All trailing alphanumerics are taken as digits so
+// that a digit outside the radix is an error rather than the start of the next token.
+fn radix_integer<T: Integer>(input: &str) -> IResult<&str, T> {
     map_res(
-        recognize(pair(
-            opt(complete::char('-')),
-            many1(terminated(one_of("0123456789"), many0(complete::char('_')))),
+        tuple((
+            opt(one_of("+-")),
+            complete::char('0'),
+            one_of("xob"),
+            take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_'),
         )),
-        |out: &str| T::from_str(&str::replace(&out, "_", "")),
+        |(sign, _, prefix, digits): (Option<char>, char, char, &str)| {
+            let radix = match prefix {
+                'x' => 16,
+                'o' => 8,
+                _ => 2,
+            };
+            let digits = str::replace(digits, "_", "");
+            match sign {
+                Some('-') => T::from_str_radix(&format!("-{}", digits), radix),
+                _ => T::from_str_radix(&digits, radix),
+            }
+        },
     )(input)
 }
 
-pub fn float<'a, T: Float + FromStr>(input: &'a str) -> IResult<&'a str, T> {
+/// Whether `rest`, what follows the digits of an integer, continues it as a
+/// float with a fraction or an exponent, like the `.5` in `1.5` or the `e10`
+/// in `1e10`. A malformed exponent like the `e--5` in `1e--5` counts too, so
+/// that it's read, and rejected, as part of the number.
+pub fn is_float_suffix(rest: &str) -> bool {
+    rest.starts_with(&['.', 'e', 'E'][..])
+}
+
+pub fn float<T: Float + FromStr>(input: &str) -> IResult<&str, T> {
+    alt((non_finite, finite))(input)
+}
+
+// succeeds when the previous word has ended, i.e. isn't followed by identifier characters
+fn word_end(input: &str) -> IResult<&str, ()> {
+    not(take_while1(|c: char| c.is_alphanumeric() || c == '_'))(input)
+}
+
+// inf, infinity and nan in any case, with an optional sign. Like the other
+// keywords they must end the word, so keys like `info` or `nancy` aren't floats.
+fn non_finite<T: Float>(input: &str) -> IResult<&str, T> {
+    let (rest, (sign, word)) = terminated(
+        pair(
+            opt(one_of("+-")),
+            alt((
+                tag_no_case("infinity"),
+                tag_no_case("inf"),
+                tag_no_case("nan"),
+            )),
+        ),
+        word_end,
+    )(input)?;
+    let flt = if word.eq_ignore_ascii_case("nan") {
+        T::nan()
+    } else if sign == Some('-') {
+        T::neg_infinity()
+    } else {
+        T::infinity()
+    };
+    Ok((rest, flt))
+}
+
+// The sign is only allowed on the mantissa and right after the `e`, so
+// each is read once: `1e-5` is fine and `1e--5` or `1.-5` is an error.
+fn finite<T: Float + FromStr>(input: &str) -> IResult<&str, T> {
     map_res(
-        alt((
-            // Case one: .42
-            recognize(tuple((
-                complete::char('.'),
-                integer::<i64>,
-                opt(tuple((one_of("eE"), opt(one_of("+-")), integer::<i64>))),
-            ))), // Case two: 42e42 and 42.42e42
-            recognize(tuple((
-                integer::<i64>,
-                opt(preceded(complete::char('.'), integer::<i64>)),
-                one_of("eE"),
-                opt(one_of("+-")),
-                integer::<i64>,
-            ))), // Case three: 42. and 42.42
-            recognize(tuple((
-                integer::<i64>,
-                complete::char('.'),
-                opt(integer::<i64>),
-            ))), // Case four: 42
-            recognize(integer::<i64>)
-        )),
-        |out: &str| T::from_str(out),
+        terminated(
+            alt((
+                // Case one: .42 and .42e42
+                recognize(tuple((complete::char('.'), digits, opt(exponent)))),
+                // Case two: 42, 42., 42.42, 42e42 and 42.42e42
+                recognize(tuple((
+                    decimal_digits,
+                    opt(preceded(complete::char('.'), opt(digits))),
+                    opt(exponent),
+                ))),
+            )),
+            // a bad exponent or fraction, or a radix prefix, isn't left for
+            // the next token
+            not(take_while1(|c: char| {
+                c.is_alphanumeric() || c == '_' || c == '.' || c == '+' || c == '-'
+            })),
+        ),
+        |out: &str| {
+            let out = out.strip_prefix('+').unwrap_or(out);
+            T::from_str(&str::replace(out, "_", ""))
+        },
     )(input)
 }
 
-pub fn boolean<'a>(input: &'a str) -> IResult<&'a str, bool> {
-    alt((value(false, tag("false")), value(true, tag("true"))))(input)
+pub fn boolean(input: &str) -> IResult<&str, bool> {
+    terminated(
+        alt((value(false, tag("false")), value(true, tag("true")))),
+        word_end,
+    )(input)
 }
 
-pub fn null<'a>(input: &'a str) -> IResult<&'a str, ()> {
-    value((), tag("null"))(input)
+pub fn null(input: &str) -> IResult<&str, ()> {
+    value((), terminated(tag("null"), word_end))(input)
 }
@@ -1,34 +1,153 @@
-use escape8259::{unescape, UnescapeError};
-use std::error::Error;
-
-fn parse_string<'a>(input: &'a str) -> Result<(&'a str, String), Box<dyn Error>> {
-    let mut escp = false;
-    let mut first = true;
-    let mut s = "".to_string();
-    for (i, c) in input.char_indices() {
-        if c != '"' && first {
-            return Err("not a string".into());
-        } else if c == '\\' && !escp {
-            escp = true;
-        } else if c == '"' && !escp && !first {
-            return Ok((&input[(i + 1)..input.len()], s));
-        } else if !c.is_whitespace() {
-            escp = false;
+use std::{borrow::Cow, str::CharIndices};
+
+/// Why a string literal couldn't be parsed. Offsets are in bytes from the
+/// start of the input.
+#[derive(Debug, PartialEq)]
+pub enum StringError {
+    /// The input doesn't start with a `"`.
+    NotAString,
+    /// The closing `"` is missing.
+    Unterminated,
+    /// The escape sequence at this offset isn't recognized.
+    InvalidEscape(usize),
+    /// The unicode escape at this offset isn't a valid unicode scalar value,
+    /// e.g. a lone surrogate or a value above 0x10FFFF.
+    InvalidCodePoint(usize),
+}
+
+/// Why `${VAR}` interpolation failed.
+#[derive(Debug, PartialEq)]
+pub enum InterpolateError {
+    /// A `${` has no closing `}`.
+    Unterminated,
+    /// The named variable isn't defined.
+    Undefined(String),
+}
+
+/// Parses a quoted string, decoding the JSON escapes `\" \\ \/ \b \f \n \r \t`
+/// and `\uXXXX`, plus `\u{X..}` with 1 to 6 hex digits. Any other escape is
+/// an error. Characters other than `"` and `\` are taken literally, so raw
+/// tabs, newlines and other control characters are kept as they are.
+///
+/// Strings without escapes are borrowed from the input rather than copied.
+pub fn string(input: &str) -> Result<(&str, Cow<'_, str>), StringError> {
+    if !input.starts_with('"') {
+        return Err(StringError::NotAString);
+    }
+    let body = &input[1..];
+    match body.find(&['"', '\\'][..]) {
+        Some(end) if body[end..].starts_with('"') => {
+            Ok((&body[(end + 1)..], Cow::Borrowed(&body[..end])))
         }
-        if !first {
-            if c == '\n' {
-                s += "\\n";
-            } else if c == '\r' {
-                s += "\\r";
-            } else {
-                s += &format!("{}", c);
-            }
+        Some(_) => unescaped(input).map(|(rest, s)| (rest, Cow::Owned(s))),
+        None => Err(StringError::Unterminated),
+    }
+}
+
+fn unescaped(input: &str) -> Result<(&str, String), StringError> {
+    let mut s = String::new();
+    let mut chars = input.char_indices();
+    chars.next();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((&input[(i + 1)..], s)),
+            '\\' => s.push(escape(i, &mut chars)?),
+            _ => s.push(c),
         }
-        first = false;
     }
-    return Err("unclosed string".into());
+    Err(StringError::Unterminated)
 }
 
-pub fn string<'a>(input: &'a str) -> Result<(&str, Result<String, UnescapeError>), Box<dyn Error>> {
-    return parse_string(input).map(|out: (&str, String)| (out.0, unescape(&out.1)));
+// parses the escape after the backslash at `start`
+fn escape(start: usize, chars: &mut CharIndices) -> Result<char, StringError> {
+    let c = match chars.next() {
+        Some((_, c)) => c,
+        None => return Err(StringError::Unterminated),
+    };
+    Ok(match c {
+        '"' => '"',
+        '\\' => '\\',
+        '/' => '/',
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'u' => return unicode_escape(start, chars),
+        _ => return Err(StringError::InvalidEscape(start)),
+    })
+}
+
+// \u{1F600} takes 1 to 6 hex digits. \u00e9 takes exactly 4, and astral
+// characters are written as a surrogate pair, e.g. \uD83D\uDE00.
+fn unicode_escape(start: usize, chars: &mut CharIndices) -> Result<char, StringError> {
+    let braced = chars.as_str().starts_with('{');
+    if braced {
+        chars.next();
+        let digits = chars.as_str();
+        let len = digits.find('}').ok_or(StringError::InvalidEscape(start))?;
+        if len == 0 || len > 6 || !digits[..len].chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(StringError::InvalidEscape(start));
+        }
+        let code = u32::from_str_radix(&digits[..len], 16).unwrap();
+        chars.nth(len);
+        return std::char::from_u32(code).ok_or(StringError::InvalidCodePoint(start));
+    }
+    let high = hex4(start, chars)?;
+    if !(0xD800..0xDC00).contains(&high) {
+        return std::char::from_u32(high).ok_or(StringError::InvalidCodePoint(start));
+    }
+    // a high surrogate must be followed by an escaped low surrogate
+    if !chars.as_str().starts_with("\\u") {
+        return Err(StringError::InvalidCodePoint(start));
+    }
+    chars.nth(1);
+    let low = hex4(start, chars)?;
+    if !(0xDC00..0xE000).contains(&low) {
+        return Err(StringError::InvalidCodePoint(start));
+    }
+    let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+    std::char::from_u32(code).ok_or(StringError::InvalidCodePoint(start))
+}
+
+fn hex4(start: usize, chars: &mut CharIndices) -> Result<u32, StringError> {
+    let digits = match chars.as_str().get(..4) {
+        Some(digits) if digits.chars().all(|c| c.is_ascii_hexdigit()) => digits,
+        _ => return Err(StringError::InvalidEscape(start)),
+    };
+    chars.nth(3);
+    Ok(u32::from_str_radix(digits, 16).unwrap())
+}
+
+/// Replaces each `${NAME}` in a decoded string with `lookup(NAME)`, and each
+/// `$$` with a single `$`. Any other `$` is kept as it is.
+pub fn interpolate<'a>(
+    s: Cow<'a, str>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Cow<'a, str>, InterpolateError> {
+    if !s.contains('$') {
+        return Ok(s);
+    }
+    let mut out = String::new();
+    let mut rest = &*s;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after.find('}').ok_or(InterpolateError::Unterminated)?;
+            let name = &after[..end];
+            let value =
+                lookup(name).ok_or_else(|| InterpolateError::Undefined(name.to_string()))?;
+            out.push_str(&value);
+            rest = &after[(end + 1)..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(Cow::Owned(out))
 }
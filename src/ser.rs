@@ -1,30 +1,361 @@
+use crate::parsing;
 use escape8259::escape;
 use serde::{ser, Serialize};
-use std::{error, fmt::Display};
+use std::{
+    error,
+    fmt::Display,
+    io::{self, Write},
+};
 
-pub struct Serializer {
-    // This string starts empty and JSON is appended as values are serialized.
-    output: String,
+pub struct Serializer<W> {
+    // JACL is written here as values are serialized.
+    writer: W,
+    options: SerOptions,
+    // how many containers we're inside of, for indentation
+    depth: usize,
+    // whether the innermost open container has no entries yet
+    is_first: bool,
+    // with `sort_keys` or `NoneAs::Skip`, the already serialized entries of
+    // each open map
+    pending: Vec<Vec<(Vec<u8>, Vec<u8>)>>,
+    // set by a `STRUCT_NAME` newtype so that the map inside it is written as
+    // a struct
+    struct_map: bool,
+    // set by a `BIGINT_NAME` newtype so that the digits inside it are written
+    // as a bare integer
+    big_int: bool,
+    // set while serializing the key of a struct written from a map, so that
+    // a string key is written like a field name
+    struct_key: bool,
+    // the closing delimiter of each open map
+    map_closers: Vec<&'static str>,
 }
 
+// `Value::Struct` serializes as a newtype struct with this name around its
+// fields, since serde's data model has no struct with runtime field names.
+// Other serializers see an ordinary newtype and write a map.
+pub(crate) const STRUCT_NAME: &str = "$serde_jacl::private::Struct";
+
+/// Output options for [`to_string_with_options`] and [`to_writer_with_options`].
+#[derive(Clone, Debug, Default)]
+pub struct SerOptions {
+    pretty: Option<PrettyConfig>,
+    sort_keys: bool,
+    none_as: NoneAs,
+    separator: Separator,
+    trailing_newline: bool,
+    quote_keys: QuoteKeys,
+}
+
+/// When the field names of structs are quoted. This covers the keys of a
+/// [`Value::Struct`](crate::structs::Value::Struct) too. Keys of maps in
+/// `{ }` are always quoted, since JACL reads them as strings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuoteKeys {
+    /// `("name" : 1)`, as in JSON.
+    Always,
+    /// `(name : 1 "max-conns" : 2)`, with only names that aren't
+    /// identifiers quoted.
+    #[default]
+    WhenNeeded,
+    /// `(name : 1)`, and an error for a name that isn't an identifier.
+    Never,
+}
+
+/// What goes between the elements of a sequence and the entries of a map or
+/// struct, where they aren't each on their own line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Separator {
+    /// `[1 2 3]`
+    #[default]
+    Space,
+    /// `[1, 2, 3]`, which can also be read with strict commas. Pretty output
+    /// ends each line but the last with a comma.
+    Comma,
+    /// Each element on a new line, without indentation.
+    Newline,
+}
+
+/// How map entries and struct fields holding `None` are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NoneAs {
+    /// Write the entry as `key : null`.
+    #[default]
+    Null,
+    /// Leave the entry out. This applies to any value written as `null`,
+    /// including `()` and `Value::Literal(Literal::Null)`, which read back
+    /// the same as `None`.
+    Skip,
+}
+
+impl SerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lay the output out over multiple lines. The default is the compact form.
+    pub fn with_pretty(mut self, config: PrettyConfig) -> Self {
+        self.pretty = Some(config);
+        self
+    }
+
+    /// Write map entries ordered by key, so the same map always produces the
+    /// same output. Struct fields keep their declaration order.
+    pub fn with_sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Whether entries holding `None` are written as `null` or left out.
+    /// Sequence elements are always written, so their positions don't shift.
+    pub fn with_none_as(mut self, none_as: NoneAs) -> Self {
+        self.none_as = none_as;
+        self
+    }
+
+    /// What separates elements and entries. The default is a space.
+    pub fn with_separator(mut self, separator: Separator) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// End the output with a newline, as POSIX text files do.
+    pub fn with_trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+
+    /// When struct field names are quoted. The default quotes only names
+    /// that couldn't be read back bare.
+    pub fn with_quote_keys(mut self, quote_keys: QuoteKeys) -> Self {
+        self.quote_keys = quote_keys;
+        self
+    }
+}
+
+/// Layout options for [`to_string_pretty`].
+#[derive(Clone, Debug)]
+pub struct PrettyConfig {
+    indent: String,
+    seq_per_line: bool,
+}
+
+impl Default for PrettyConfig {
+    /// Four space indents, with sequences kept on one line.
+    fn default() -> Self {
+        PrettyConfig {
+            indent: "    ".to_string(),
+            seq_per_line: false,
+        }
+    }
+}
+
+impl PrettyConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The string repeated once per level of nesting, e.g. two spaces or a tab.
+    pub fn with_indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = indent.into();
+        self
+    }
+
+    /// Whether each sequence element goes on its own line, like map and
+    /// struct entries do.
+    pub fn with_seq_per_line(mut self, seq_per_line: bool) -> Self {
+        self.seq_per_line = seq_per_line;
+        self
+    }
+}
+
+impl<W: Write> Serializer<W> {
+    fn new(writer: W, options: SerOptions) -> Self {
+        Serializer {
+            writer,
+            options,
+            depth: 0,
+            is_first: false,
+            pending: Vec::new(),
+            struct_map: false,
+            big_int: false,
+            struct_key: false,
+            map_closers: Vec::new(),
+        }
+    }
+
+    // serializes `value` on its own, as it would be written at the current depth
+    fn render<T>(&self, value: &T) -> Result<Vec<u8>, JaclSerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = Serializer::new(Vec::new(), self.options.clone());
+        serializer.depth = self.depth;
+        serializer.struct_key = self.struct_key;
+        value.serialize(&mut serializer)?;
+        Ok(serializer.writer)
+    }
+
+    fn skips_none(&self) -> bool {
+        self.options.none_as == NoneAs::Skip
+    }
+
+    // map entries are buffered when they may be reordered or left out
+    fn buffers_entries(&self) -> bool {
+        self.options.sort_keys || self.skips_none()
+    }
+
+    fn field<T>(&mut self, key: &str, value: &T) -> Result<(), JaclSerError>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.skips_none() {
+            let value = self.render(value)?;
+            if value == b"null" {
+                return Ok(());
+            }
+            self.separate(false)?;
+            self.field_name(key)?;
+            self.key_separator()?;
+            return Ok(self.writer.write_all(&value)?);
+        }
+        self.separate(false)?;
+        self.field_name(key)?;
+        self.key_separator()?;
+        value.serialize(self)
+    }
+
+    // with `QuoteKeys::WhenNeeded`, field names are bare where they parse as
+    // an identifier, and quoted otherwise, e.g. one renamed to `a-b`
+    fn field_name(&mut self, key: &str) -> Result<(), JaclSerError> {
+        let is_identifier = matches!(parsing::identifier(key), Ok(("", _)));
+        match self.options.quote_keys {
+            QuoteKeys::Always => ser::Serializer::serialize_str(self, key),
+            QuoteKeys::WhenNeeded | QuoteKeys::Never if is_identifier => self.write(key),
+            QuoteKeys::WhenNeeded => ser::Serializer::serialize_str(self, key),
+            QuoteKeys::Never => Err(ser::Error::custom(format!(
+                "`{}` isn't an identifier, so it can't be written unquoted",
+                key
+            ))),
+        }
+    }
+
+    fn write(&mut self, s: &str) -> Result<(), JaclSerError> {
+        Ok(self.writer.write_all(s.as_bytes())?)
+    }
+
+    fn newline(&mut self) -> Result<(), JaclSerError> {
+        let indent = match &self.options.pretty {
+            Some(config) => config.indent.repeat(self.depth),
+            None => return Ok(()),
+        };
+        self.write("\n")?;
+        self.write(&indent)
+    }
+
+    // whether entries of this kind of container go on their own lines
+    fn is_multiline(&self, is_seq: bool) -> bool {
+        match &self.options.pretty {
+            Some(config) => !is_seq || config.seq_per_line,
+            None => false,
+        }
+    }
+
+    fn begin(&mut self, open: &str) -> Result<(), JaclSerError> {
+        self.write(open)?;
+        self.depth += 1;
+        self.is_first = true;
+        Ok(())
+    }
+
+    // separates a container entry from whatever came before it
+    fn separate(&mut self, is_seq: bool) -> Result<(), JaclSerError> {
+        let is_first = self.is_first;
+        self.is_first = false;
+        if !is_first && self.options.separator == Separator::Comma {
+            self.write(",")?;
+        }
+        if self.is_multiline(is_seq) {
+            self.newline()
+        } else if !is_first {
+            match self.options.separator {
+                Separator::Space | Separator::Comma => self.write(" "),
+                Separator::Newline => self.write("\n"),
+            }
+        } else {
+            Ok(())
+        }
+    }
+
+    fn finish(&mut self, is_seq: bool, close: &str) -> Result<(), JaclSerError> {
+        let is_empty = self.is_first;
+        self.is_first = false;
+        self.depth -= 1;
+        if !is_empty && self.is_multiline(is_seq) {
+            self.newline()?;
+        }
+        self.write(close)
+    }
+
+    fn key_separator(&mut self) -> Result<(), JaclSerError> {
+        if self.options.pretty.is_some() {
+            self.write(" : ")
+        } else {
+            self.write(":")
+        }
+    }
+}
+
+/// What went wrong while serializing.
 #[derive(Debug)]
-pub struct JaclSerError;
+pub enum ErrorKind {
+    /// A `Serialize` impl failed through `ser::Error::custom`, e.g. a
+    /// `Mutex` that is poisoned. The message is kept as given.
+    Custom(String),
+    /// Writing the output failed, e.g. when [`to_writer`] is given a full
+    /// buffer or a closed pipe.
+    Io(io::Error),
+}
+
+#[derive(Debug)]
+pub struct JaclSerError {
+    kind: ErrorKind,
+}
+
+impl JaclSerError {
+    fn new(kind: ErrorKind) -> Self {
+        JaclSerError { kind }
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
 
 impl Display for JaclSerError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        write!(f, "can't parse data")?;
+        match &self.kind {
+            ErrorKind::Custom(msg) => write!(f, "error: {}", msg)?,
+            ErrorKind::Io(err) => write!(f, "io error: {}", err)?,
+        }
         Ok(())
     }
 }
 
 impl error::Error for JaclSerError {}
 
+impl From<io::Error> for JaclSerError {
+    fn from(err: io::Error) -> Self {
+        JaclSerError::new(ErrorKind::Io(err))
+    }
+}
+
 impl ser::Error for JaclSerError {
-    fn custom<T>(f: T) -> Self
+    fn custom<T>(msg: T) -> Self
     where
         T: std::fmt::Display,
     {
-        JaclSerError
+        JaclSerError::new(ErrorKind::Custom(msg.to_string()))
     }
 }
 
@@ -32,19 +363,73 @@ impl ser::Error for JaclSerError {
 // functions such as `to_string`, `to_bytes`, or `to_writer` depending on what
 // Rust types the serializer is able to produce as output.
 //
-// This basic serializer supports only `to_string`.
+// This serializer writes into any `io::Write`, and `to_string` collects that
+// into a `String`.
 pub fn to_string<T>(value: &T) -> Result<String, JaclSerError>
 where
     T: Serialize,
 {
-    let mut serializer = Serializer {
-        output: String::new(),
-    };
+    let bytes = to_vec(value)?;
+    Ok(String::from_utf8(bytes).expect("serializer wrote invalid utf-8"))
+}
+
+/// Serializes `value` straight into `writer`, without building a `String`.
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<(), JaclSerError>
+where
+    W: Write,
+    T: Serialize,
+{
+    to_writer_with_options(writer, value, &SerOptions::default())
+}
+
+/// Like [`to_writer`], but laid out according to `options`.
+pub fn to_writer_with_options<W, T>(
+    writer: W,
+    value: &T,
+    options: &SerOptions,
+) -> Result<(), JaclSerError>
+where
+    W: Write,
+    T: Serialize,
+{
+    let mut serializer = Serializer::new(writer, options.clone());
     value.serialize(&mut serializer)?;
-    Ok(serializer.output)
+    if options.trailing_newline {
+        serializer.write("\n")?;
+    }
+    Ok(())
+}
+
+/// Serializes `value` into a byte vector.
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>, JaclSerError>
+where
+    T: Serialize,
+{
+    let mut bytes = Vec::new();
+    to_writer(&mut bytes, value)?;
+    Ok(bytes)
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+/// Like [`to_string`], but with each map and struct entry on its own
+/// indented line, laid out according to `config`.
+pub fn to_string_pretty<T>(value: &T, config: PrettyConfig) -> Result<String, JaclSerError>
+where
+    T: Serialize,
+{
+    to_string_with_options(value, &SerOptions::new().with_pretty(config))
+}
+
+/// Like [`to_string`], but laid out according to `options`.
+pub fn to_string_with_options<T>(value: &T, options: &SerOptions) -> Result<String, JaclSerError>
+where
+    T: Serialize,
+{
+    let mut bytes = Vec::new();
+    to_writer_with_options(&mut bytes, value, options)?;
+    Ok(String::from_utf8(bytes).expect("serializer wrote invalid utf-8"))
+}
+
+impl<W: Write> ser::Serializer for &mut Serializer<W> {
     // The output type produced by this `Serializer` during successful
     // serialization. Most serializers that produce text or binary output should
     // set `Ok = ()` and serialize into an `io::Write` or buffer contained
@@ -72,8 +457,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     // of the primitive types of the data model and map it to JSON by appending
     // into the output string.
     fn serialize_bool(self, v: bool) -> Result<(), JaclSerError> {
-        self.output += if v { "true" } else { "false" };
-        Ok(())
+        self.write(if v { "true" } else { "false" })
     }
 
     // JSON does not distinguish between different sizes of integers, so all
@@ -95,8 +479,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     // Not particularly efficient but this is example code anyway. A more
     // performant approach would be to use the `itoa` crate.
     fn serialize_i64(self, v: i64) -> Result<(), JaclSerError> {
-        self.output += &v.to_string();
-        Ok(())
+        self.write(&v.to_string())
     }
 
     fn serialize_u8(self, v: u8) -> Result<(), JaclSerError> {
@@ -112,30 +495,36 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_u64(self, v: u64) -> Result<(), JaclSerError> {
-        self.output += &v.to_string();
-        Ok(())
+        self.write(&v.to_string())
     }
 
+    // Floats are written with `{:?}`, which keeps the `.0` of whole numbers
+    // and the sign of `-0.0`, so they read back as floats, and writes the
+    // special values as `inf`, `-inf` and `NaN`, which JACL reads too.
     fn serialize_f32(self, v: f32) -> Result<(), JaclSerError> {
-        self.serialize_f64(f64::from(v))
+        self.write(&format!("{:?}", v))
     }
 
     fn serialize_f64(self, v: f64) -> Result<(), JaclSerError> {
-        self.output += &v.to_string();
-        Ok(())
+        self.write(&format!("{:?}", v))
     }
 
-    // Serialize a char as a single-character string. Other formats may
-    // represent this differently.
+    // Serialize a char as a single-character string, escaped like any other
+    // string. Other formats may represent this differently.
     fn serialize_char(self, v: char) -> Result<(), JaclSerError> {
-        self.serialize_str(&v.to_string())
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
     }
 
     fn serialize_str(self, v: &str) -> Result<(), JaclSerError> {
-        self.output += "\"";
-        self.output += &escape(v);
-        self.output += "\"";
-        Ok(())
+        if std::mem::take(&mut self.struct_key) {
+            return self.field_name(v);
+        }
+        if std::mem::take(&mut self.big_int) {
+            return self.write(v);
+        }
+        self.write("\"")?;
+        self.write(&escape(v))?;
+        self.write("\"")
     }
 
     // Serialize a byte array as an array of bytes. Could also use a base64
@@ -170,8 +559,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     // In Serde, unit means an anonymous value containing no data. Map this to
     // JSON as `null`.
     fn serialize_unit(self) -> Result<(), JaclSerError> {
-        self.output += "null";
-        Ok(())
+        self.write("null")
     }
 
     // Unit struct means a named value containing no data. Again, since there is
@@ -185,21 +573,29 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     // can choose whether to keep track of it by index or by name. Binary
     // formats typically use the index of the variant and human-readable formats
     // typically use the name.
+    //
+    // JACL uses the name, written as a string. The deserializer reads a string
+    // anywhere an enum is expected as a unit variant.
     fn serialize_unit_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<(), JaclSerError> {
-        return Err(JaclSerError);
+        self.serialize_str(variant)
     }
 
     // As is done here, serializers are encouraged to treat newtype structs as
     // insignificant wrappers around the data they contain.
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<(), JaclSerError>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<(), JaclSerError>
     where
         T: ?Sized + Serialize,
     {
+        self.struct_map = name == STRUCT_NAME;
+        #[cfg(feature = "bigint")]
+        {
+            self.big_int = name == crate::bigint::BIGINT_NAME;
+        }
         value.serialize(self)
     }
 
@@ -207,18 +603,20 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     // methods) refer exclusively to the "externally tagged" enum
     // representation.
     //
-    // Serialize this to JSON in externally tagged form as `{ NAME: VALUE }`.
+    // Serialize this to JACL in externally tagged form as `NAME : VALUE`.
     fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
-        _value: &T,
+        variant: &'static str,
+        value: &T,
     ) -> Result<(), JaclSerError>
     where
         T: ?Sized + Serialize,
     {
-        return Err(JaclSerError);
+        self.write(variant)?;
+        self.key_separator()?;
+        value.serialize(self)
     }
 
     // Now we get to the serialization of compound types.
@@ -232,7 +630,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     // explicitly in the serialized form. Some serializers may only be able to
     // support sequences for which the length is known up front.
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, JaclSerError> {
-        self.output += "[";
+        self.begin("[")?;
         Ok(self)
     }
 
@@ -253,24 +651,34 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_seq(Some(len))
     }
 
-    // Tuple variants are represented in JSON as `{ NAME: [DATA...] }`. Again
+    // Tuple variants are represented in JACL as `NAME : [DATA...]`. Again
     // this method is only responsible for the externally tagged representation.
     fn serialize_tuple_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, JaclSerError> {
-        return Err(JaclSerError);
+        self.write(variant)?;
+        self.key_separator()?;
+        self.begin("[")?;
+        Ok(self)
     }
 
     // Maps are represented in JSON as `{ K: V, K: V, ... }`.
-    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, JaclSerError> {
-        if len.is_none() {
-            self.output += "{";
+    // Keys are written as they serialize, so string keys are quoted and
+    // integer and bool keys are bare, e.g. `{"a" : 1}` and `{42 : "b"}`.
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, JaclSerError> {
+        let (open, close) = if std::mem::take(&mut self.struct_map) {
+            ("(", ")")
         } else {
-            self.output += "(";
+            ("{", "}")
+        };
+        self.begin(open)?;
+        self.map_closers.push(close);
+        if self.buffers_entries() {
+            self.pending.push(Vec::new());
         }
         Ok(self)
     }
@@ -285,58 +693,58 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, JaclSerError> {
-        self.output += "(";
+        self.begin("(")?;
         Ok(self)
     }
 
-    // Struct variants are represented in JSON as `{ NAME: { K: V, ... } }`.
+    // Struct variants are represented in JACL as `NAME : ( K : V ... )`.
     // This is the externally tagged representation.
     fn serialize_struct_variant(
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, JaclSerError> {
-        return Err(JaclSerError);
+        self.write(variant)?;
+        self.key_separator()?;
+        self.begin("(")?;
+        Ok(self)
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl<W: Write> ser::SerializeTupleVariant for &mut Serializer<W> {
     type Ok = ();
 
     type Error = JaclSerError;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        return Err(JaclSerError);
+        self.separate(true)?;
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        return Err(JaclSerError);
+        self.finish(true, "]")
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl<W: Write> ser::SerializeStructVariant for &mut Serializer<W> {
     type Ok = ();
 
     type Error = JaclSerError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        _key: &'static str,
-        _value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        return Err(JaclSerError);
+        self.field(key, value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        return Err(JaclSerError);
+        self.finish(false, ")")
     }
 }
 
@@ -347,7 +755,7 @@ impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
 //
 // This impl is SerializeSeq so these methods are called after `serialize_seq`
 // is called on the Serializer.
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl<W: Write> ser::SerializeSeq for &mut Serializer<W> {
     // Must match the `Ok` type of the serializer.
     type Ok = ();
     // Must match the `Error` type of the serializer.
@@ -358,21 +766,18 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        if !self.output.ends_with('[') {
-            self.output += " ";
-        }
+        self.separate(true)?;
         value.serialize(&mut **self)
     }
 
     // Close the sequence.
     fn end(self) -> Result<(), JaclSerError> {
-        self.output += "]";
-        Ok(())
+        self.finish(true, "]")
     }
 }
 
 // Same thing but for tuples.
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl<W: Write> ser::SerializeTuple for &mut Serializer<W> {
     type Ok = ();
     type Error = JaclSerError;
 
@@ -380,20 +785,17 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        if !self.output.ends_with('[') {
-            self.output += " ";
-        }
+        self.separate(true)?;
         value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<(), JaclSerError> {
-        self.output += "]";
-        Ok(())
+        self.finish(true, "]")
     }
 }
 
 // Same thing but for tuple structs.
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl<W: Write> ser::SerializeTupleStruct for &mut Serializer<W> {
     type Ok = ();
     type Error = JaclSerError;
 
@@ -401,15 +803,12 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        if !self.output.ends_with('[') {
-            self.output += " ";
-        }
+        self.separate(true)?;
         value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<(), JaclSerError> {
-        self.output += "]";
-        Ok(())
+        self.finish(true, "]")
     }
 }
 
@@ -421,7 +820,7 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
 // `serialize_entry` method allows serializers to optimize for the case where
 // key and value are both available simultaneously. In JSON it doesn't make a
 // difference so the default behavior for `serialize_entry` is fine.
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl<W: Write> ser::SerializeMap for &mut Serializer<W> {
     type Ok = ();
     type Error = JaclSerError;
 
@@ -437,10 +836,18 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        if !self.output.ends_with('{') && !self.output.ends_with('(') {
-            self.output += " ";
-        }
-        key.serialize(&mut **self)
+        self.struct_key = self.map_closers.last() == Some(&")");
+        let written = if self.buffers_entries() {
+            self.render(key).map(|key| {
+                self.pending.last_mut().unwrap().push((key, Vec::new()));
+            })
+        } else {
+            self.separate(false)
+                .and_then(|_| key.serialize(&mut **self))
+        };
+        // a key that isn't a string doesn't use the flag
+        self.struct_key = false;
+        written
     }
 
     // It doesn't make a difference whether the colon is printed at the end of
@@ -450,19 +857,41 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        self.output += ":";
+        if self.buffers_entries() {
+            let value = self.render(value)?;
+            let entries = self.pending.last_mut().unwrap();
+            if self.options.none_as == NoneAs::Skip && value == b"null" {
+                entries.pop();
+            } else {
+                entries.last_mut().unwrap().1 = value;
+            }
+            return Ok(());
+        }
+        self.key_separator()?;
         value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<(), JaclSerError> {
-        self.output += "}";
-        Ok(())
+        if self.buffers_entries() {
+            let mut entries = self.pending.pop().unwrap();
+            if self.options.sort_keys {
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+            }
+            for (key, value) in entries {
+                self.separate(false)?;
+                self.writer.write_all(&key)?;
+                self.key_separator()?;
+                self.writer.write_all(&value)?;
+            }
+        }
+        let close = self.map_closers.pop().unwrap();
+        self.finish(false, close)
     }
 }
 
 // Structs are like maps in which the keys are constrained to be compile-time
 // constant strings.
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl<W: Write> ser::SerializeStruct for &mut Serializer<W> {
     type Ok = ();
     type Error = JaclSerError;
 
@@ -470,17 +899,11 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        if !self.output.ends_with('(') {
-            self.output += " ";
-        }
-        self.output += key;
-        self.output += ":";
-        value.serialize(&mut **self)
+        self.field(key, value)
     }
 
     fn end(self) -> Result<(), JaclSerError> {
-        self.output += ")";
-        Ok(())
+        self.finish(false, ")")
     }
 }
 
@@ -498,3 +921,486 @@ fn test_struct() {
     };
     assert_eq!(to_string(&test).unwrap(), r#"(int:1 seq:[" \"a\" " "b"])"#);
 }
+
+#[test]
+fn test_pretty() {
+    #[derive(Serialize)]
+    struct Inner {
+        int: u32,
+        empty: Vec<u32>,
+    }
+
+    #[derive(Serialize)]
+    struct Test {
+        name: &'static str,
+        seq: Vec<u32>,
+        inner: Inner,
+    }
+
+    let test = Test {
+        name: "a",
+        seq: vec![1, 2],
+        inner: Inner {
+            int: 1,
+            empty: vec![],
+        },
+    };
+    let two = "(
+  name : \"a\"
+  seq : [1 2]
+  inner : (
+    int : 1
+    empty : []
+  )
+)";
+    let config = PrettyConfig::new().with_indent("  ");
+    assert_eq!(two, to_string_pretty(&test, config).unwrap());
+    let four = "(
+    name : \"a\"
+    seq : [
+        1
+        2
+    ]
+    inner : (
+        int : 1
+        empty : []
+    )
+)";
+    let config = PrettyConfig::new().with_seq_per_line(true);
+    assert_eq!(four, to_string_pretty(&test, config).unwrap());
+}
+
+#[test]
+fn test_writer() {
+    #[derive(Serialize)]
+    struct Test {
+        int: u32,
+        seq: Vec<&'static str>,
+    }
+
+    let test = Test {
+        int: 1,
+        seq: vec!["a", "b"],
+    };
+    let mut bytes = Vec::new();
+    to_writer(&mut bytes, &test).unwrap();
+    assert_eq!(to_string(&test).unwrap().into_bytes(), bytes);
+
+    // io errors are returned rather than panicking
+    let mut buf = [0u8; 4];
+    let err = to_writer(&mut buf[..], &test).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Io(_)));
+}
+
+#[test]
+fn test_vec() {
+    #[derive(Serialize)]
+    struct Test {
+        name: &'static str,
+        nested: Vec<(u8, f64)>,
+    }
+
+    let test = Test {
+        name: "λ",
+        nested: vec![(1, 0.5), (2, -1.0)],
+    };
+    assert_eq!(
+        to_string(&test).unwrap().into_bytes(),
+        to_vec(&test).unwrap()
+    );
+}
+
+#[test]
+fn test_char() {
+    #[derive(Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Test {
+        sep: char,
+    }
+
+    for (c, expected) in [
+        (',', r#"(sep:",")"#),
+        ('\n', r#"(sep:"\n")"#),
+        ('"', r#"(sep:"\"")"#),
+        ('\u{1}', r#"(sep:"\u0001")"#),
+        ('λ', r#"(sep:"λ")"#),
+        ('😀', r#"(sep:"😀")"#),
+    ] {
+        let test = Test { sep: c };
+        let s = to_string(&test).unwrap();
+        assert_eq!(expected, s);
+        assert_eq!(test, crate::de::from_str::<Test>(&s).unwrap());
+    }
+}
+
+#[test]
+fn test_sort_keys() {
+    use crate::structs::Value;
+    use std::collections::HashMap;
+
+    let map: HashMap<String, Value> = (0..20)
+        .map(|i| (format!("k{:02}", i), Value::int(i)))
+        .collect();
+    let mut value = Value::Map(HashMap::new());
+    value["b"] = Value::Map(map);
+    value["a"] = Value::Seq(vec![Value::int(1)]);
+    let options = SerOptions::new().with_sort_keys(true);
+    let first = to_string_with_options(&value, &options).unwrap();
+    assert_eq!(first, to_string_with_options(&value, &options).unwrap());
+
+    let mut value = Value::Map(HashMap::new());
+    value["c"] = Value::int(3);
+    value["a"]["z"] = Value::int(2);
+    value["a"]["y"] = Value::int(1);
+    value["b"] = Value::Seq(vec![]);
+    assert_eq!(
+        r#"{"a":{"y":1 "z":2} "b":[] "c":3}"#,
+        to_string_with_options(&value, &options).unwrap()
+    );
+    let pretty = options.with_pretty(PrettyConfig::new().with_indent("  "));
+    assert_eq!(
+        "{\n  \"a\" : {\n    \"y\" : 1\n    \"z\" : 2\n  }\n  \"b\" : []\n  \"c\" : 3\n}",
+        to_string_with_options(&value, &pretty).unwrap()
+    );
+}
+
+#[test]
+fn test_none_as() {
+    use crate::structs::Value;
+    use std::collections::HashMap;
+
+    #[derive(Serialize)]
+    struct Test {
+        a: Option<u32>,
+        b: Option<u32>,
+    }
+
+    let test = Test {
+        a: None,
+        b: Some(1),
+    };
+    assert_eq!("(a:null b:1)", to_string(&test).unwrap());
+    let null = SerOptions::new().with_none_as(NoneAs::Null);
+    assert_eq!(
+        to_string(&test).unwrap(),
+        to_string_with_options(&test, &null).unwrap()
+    );
+    let skip = SerOptions::new().with_none_as(NoneAs::Skip);
+    assert_eq!("(b:1)", to_string_with_options(&test, &skip).unwrap());
+
+    let mut value = Value::Map(HashMap::new());
+    value["a"] = Value::null();
+    value["b"] = Value::Seq(vec![Value::null()]);
+    assert_eq!(
+        r#"{"b":[null]}"#,
+        to_string_with_options(&value, &skip).unwrap()
+    );
+}
+
+#[test]
+fn test_escape() {
+    let s = "say \"hi\"\\ \t tab\nnew\r\u{0}\u{1f} /λ";
+    let out = to_string(&s).unwrap();
+    assert_eq!(r#""say \"hi\"\\ \t tab\nnew\r\u0000\u001f /λ""#, out);
+    assert_eq!(s, crate::de::from_str::<String>(&out).unwrap());
+
+    let seq = vec![s.to_string(), "\"".to_string()];
+    let out = to_string(&seq).unwrap();
+    assert_eq!(seq, crate::de::from_str::<Vec<String>>(&out).unwrap());
+}
+
+#[test]
+fn test_custom_error() {
+    struct Odd(u32);
+
+    impl Serialize for Odd {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            // `u32::is_multiple_of` needs Rust 1.87
+            #[allow(clippy::manual_is_multiple_of)]
+            if self.0 % 2 == 0 {
+                return Err(ser::Error::custom(format!("{} is even", self.0)));
+            }
+            serializer.serialize_u32(self.0)
+        }
+    }
+
+    assert_eq!("[1 3]", to_string(&[Odd(1), Odd(3)]).unwrap());
+    let err: crate::JaclSerError = to_string(&[Odd(1), Odd(2)]).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Custom(msg) if msg == "2 is even"));
+    assert_eq!("error: 2 is even", err.to_string());
+    let err: Box<dyn error::Error> = Box::new(err);
+    assert_eq!("error: 2 is even", err.to_string());
+}
+
+#[test]
+fn test_map_keys() {
+    use crate::de::from_str;
+    use std::collections::HashMap;
+
+    let ints = HashMap::from([(42u32, "a".to_string()), (7, "b".to_string())]);
+    let s = to_string(&ints).unwrap();
+    assert!(
+        s == r#"{42:"a" 7:"b"}"# || s == r#"{7:"b" 42:"a"}"#,
+        "{}",
+        s
+    );
+    assert_eq!(ints, from_str::<HashMap<u32, String>>(&s).unwrap());
+    assert_eq!(
+        ints,
+        from_str::<HashMap<u32, String>>(r#"42 : "a" 7 : "b""#).unwrap()
+    );
+
+    let bools = HashMap::from([(true, 1i64), (false, -1)]);
+    let s = to_string(&bools).unwrap();
+    assert_eq!(bools, from_str::<HashMap<bool, i64>>(&s).unwrap());
+
+    let pretty = to_string_pretty(&bools, PrettyConfig::new()).unwrap();
+    assert_eq!(bools, from_str::<HashMap<bool, i64>>(&pretty).unwrap());
+}
+
+#[test]
+fn test_tuple() {
+    use crate::de::from_str;
+
+    #[derive(Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Point(i32, String);
+
+    let tuple = (1, "a".to_string(), [true, false]);
+    let out = to_string(&tuple).unwrap();
+    assert_eq!(r#"[1 "a" [true false]]"#, out);
+    assert_eq!(tuple, from_str(out).unwrap());
+
+    let point = Point(-3, "b".into());
+    let out = to_string(&point).unwrap();
+    assert_eq!(r#"[-3 "b"]"#, out);
+    assert_eq!(point, from_str(out).unwrap());
+}
+
+#[test]
+fn test_separator() {
+    use crate::de::{from_str, Deserializer};
+    use serde::Deserialize;
+
+    #[derive(Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Test {
+        a: Vec<u32>,
+        b: (bool, char),
+    }
+    let test = Test {
+        a: vec![1, 2],
+        b: (true, 'x'),
+    };
+
+    let options = SerOptions::new().with_separator(Separator::Comma);
+    let out = to_string_with_options(&test, &options).unwrap();
+    assert_eq!(r#"(a:[1, 2], b:[true, "x"])"#, out);
+    let mut de = Deserializer::from_str(&out).with_strict_commas(true);
+    assert_eq!(test, Test::deserialize(&mut de).unwrap());
+
+    let options = SerOptions::new()
+        .with_separator(Separator::Newline)
+        .with_trailing_newline(true);
+    let out = to_string_with_options(&test, &options).unwrap();
+    assert_eq!("(a:[1\n2]\nb:[true\n\"x\"])\n", out);
+    assert_eq!(test, from_str::<Test>(out).unwrap());
+
+    let options = SerOptions::new()
+        .with_pretty(PrettyConfig::new())
+        .with_separator(Separator::Comma)
+        .with_trailing_newline(true);
+    assert_eq!(
+        "(\n    a : [1, 2],\n    b : [true, \"x\"]\n)\n",
+        to_string_with_options(&test, &options).unwrap()
+    );
+    assert_eq!(r#"(a:[1 2] b:[true "x"])"#, to_string(&test).unwrap());
+}
+
+#[test]
+fn test_skip_and_default() {
+    use crate::de::from_str;
+
+    #[derive(Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Test {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        port: Option<u16>,
+        #[serde(default = "retries")]
+        retries: u32,
+    }
+    fn retries() -> u32 {
+        3
+    }
+
+    let test = Test {
+        tags: vec![],
+        name: "a".into(),
+        port: None,
+        retries: 3,
+    };
+    let out = to_string(&test).unwrap();
+    assert_eq!(r#"(name:"a" retries:3)"#, out);
+    assert_eq!(test, from_str(out).unwrap());
+    let options = SerOptions::new().with_separator(Separator::Comma);
+    let out = to_string_with_options(&test, &options).unwrap();
+    assert_eq!(r#"(name:"a", retries:3)"#, out);
+    let out = to_string_pretty(&test, PrettyConfig::new()).unwrap();
+    assert_eq!(test, from_str(out).unwrap());
+
+    // missing fields are filled in from their defaults
+    assert_eq!(test, from_str(r#"name : "a""#).unwrap());
+    let test = Test {
+        tags: vec!["x".into()],
+        port: Some(80),
+        ..test
+    };
+    let out = to_string(&test).unwrap();
+    assert_eq!(r#"(tags:["x"] name:"a" port:80 retries:3)"#, out);
+    assert_eq!(test, from_str(out).unwrap());
+}
+
+#[test]
+fn test_field_names() {
+    use crate::de::from_str;
+    use crate::structs::Value;
+
+    #[derive(Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Test {
+        #[serde(rename = "a-b")]
+        hyphen: u32,
+        #[serde(rename = "with space")]
+        space: u32,
+        #[serde(rename = "1st")]
+        digit: u32,
+        plain_1: u32,
+    }
+    let test = Test {
+        hyphen: 1,
+        space: 2,
+        digit: 3,
+        plain_1: 4,
+    };
+    let out = to_string(&test).unwrap();
+    assert_eq!(r#"("a-b":1 "with space":2 "1st":3 plain_1:4)"#, out);
+    assert_eq!(test, from_str(&out).unwrap());
+
+    let value: Value = from_str(r#"("a-b" : 1 "with space" : (x : 2))"#).unwrap();
+    assert_eq!(value, from_str(to_string(&value).unwrap()).unwrap());
+}
+
+#[test]
+fn test_quote_keys() {
+    use crate::de::from_str;
+    use crate::structs::Value;
+    use std::collections::HashMap;
+
+    #[derive(Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Test {
+        name: u32,
+        #[serde(rename = "max-conns")]
+        max_conns: u32,
+    }
+    let test = Test {
+        name: 1,
+        max_conns: 2,
+    };
+    let value = Value::Struct(HashMap::from([("max-conns".to_string(), Value::int(2))]));
+    let map = HashMap::from([("name".to_string(), 1)]);
+
+    let options = SerOptions::new().with_quote_keys(QuoteKeys::Always);
+    let out = to_string_with_options(&test, &options).unwrap();
+    assert_eq!(r#"("name":1 "max-conns":2)"#, out);
+    assert_eq!(test, from_str(out).unwrap());
+    let out = to_string_with_options(&value, &options).unwrap();
+    assert_eq!(r#"("max-conns":2)"#, out);
+
+    let options = SerOptions::new().with_quote_keys(QuoteKeys::WhenNeeded);
+    let out = to_string_with_options(&test, &options).unwrap();
+    assert_eq!(r#"(name:1 "max-conns":2)"#, out);
+    assert_eq!(test, from_str(out).unwrap());
+    let out = to_string_with_options(&map, &options).unwrap();
+    assert_eq!(r#"{"name":1}"#, out);
+    let structure = Value::Struct(HashMap::from([("name".to_string(), Value::int(1))]));
+    let out = to_string(&structure).unwrap();
+    assert_eq!("(name:1)", out);
+    assert_eq!(structure, from_str(out).unwrap());
+
+    let options = SerOptions::new()
+        .with_quote_keys(QuoteKeys::Never)
+        .with_sort_keys(true);
+    let err = to_string_with_options(&test, &options).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Custom(msg) if msg.contains("`max-conns`")));
+    let err = to_string_with_options(&value, &options).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Custom(_)));
+    assert_eq!(
+        "(name:1)",
+        to_string_with_options(&structure, &options).unwrap()
+    );
+    // map keys are quoted whatever the option
+    assert_eq!(
+        r#"{"name":1}"#,
+        to_string_with_options(&map, &options).unwrap()
+    );
+}
+
+#[test]
+fn test_special_floats() {
+    use crate::de::from_str;
+    use crate::structs::{Literal, Number, Value};
+
+    #[derive(Serialize, serde::Deserialize, Debug)]
+    struct Test {
+        a: f64,
+        b: f32,
+        c: Vec<f64>,
+    }
+    let test = Test {
+        a: f64::INFINITY,
+        b: f32::NEG_INFINITY,
+        c: vec![f64::NAN, f64::NEG_INFINITY, -0.0, 1.0],
+    };
+    let out = to_string(&test).unwrap();
+    assert_eq!("(a:inf b:-inf c:[NaN -inf -0.0 1.0])", out);
+    let back: Test = from_str(&out).unwrap();
+    assert_eq!(f64::INFINITY, back.a);
+    assert_eq!(f32::NEG_INFINITY, back.b);
+    assert!(back.c[0].is_nan());
+    assert_eq!(f64::NEG_INFINITY, back.c[1]);
+    assert!(back.c[2] == 0.0 && back.c[2].is_sign_negative());
+
+    for flt in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN, 2.0, 0.1] {
+        let out = to_string(&Value::flt(flt)).unwrap();
+        match from_str::<Value>(&out).unwrap() {
+            Value::Literal(Literal::Number(Number::Flt(back))) => {
+                assert!(back == flt || (back.is_nan() && flt.is_nan()), "{}", out)
+            }
+            other => panic!("{} read back as {:?}", out, other),
+        }
+    }
+    assert_eq!("0.1", to_string(&0.1f32).unwrap());
+}
+
+#[test]
+fn test_unit_variants() {
+    use crate::de::from_str;
+
+    #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Debug,
+        Info,
+    }
+    #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+    struct Test {
+        level: Level,
+        levels: Vec<Level>,
+    }
+    assert_eq!("\"debug\"", to_string(&Level::Debug).unwrap());
+    let test = Test {
+        level: Level::Debug,
+        levels: vec![Level::Info, Level::Debug],
+    };
+    let out = to_string(&test).unwrap();
+    assert_eq!(r#"(level:"debug" levels:["info" "debug"])"#, out);
+    assert_eq!(test, from_str::<Test>(&out).unwrap());
+}
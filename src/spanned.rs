@@ -0,0 +1,372 @@
+//! Values that remember where in the input each part came from, for tools
+//! like formatters and language servers that need to point back into the
+//! source. Unlike [`Value`], maps and structs keep their entries in source
+//! order.
+
+use nom::{branch::alt, multi::many0};
+use std::collections::HashMap;
+
+use crate::de::{Deserializer, ErrorKind, JaclDeError, DEFAULT_MAX_DEPTH};
+use crate::parsing;
+use crate::structs::{Literal, Number, Value};
+
+/// A byte range `(start, end)` into the parsed input.
+pub type Span = (usize, usize);
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpannedValue {
+    pub kind: SpannedKind,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SpannedKind {
+    Literal(Literal),
+    Map(Vec<SpannedEntry>),
+    Struct(Vec<SpannedEntry>),
+    Seq(Vec<SpannedValue>),
+}
+
+/// A map entry or struct field. `span` runs from the start of the key to
+/// the end of the value.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpannedEntry {
+    pub key: String,
+    pub key_span: Span,
+    pub value: SpannedValue,
+    pub span: Span,
+}
+
+impl SpannedValue {
+    /// Drops the spans. Where a key appears more than once, the last entry wins.
+    pub fn into_value(self) -> Value {
+        fn entries(entries: Vec<SpannedEntry>) -> HashMap<String, Value> {
+            entries
+                .into_iter()
+                .map(|entry| (entry.key, entry.value.into_value()))
+                .collect()
+        }
+        match self.kind {
+            SpannedKind::Literal(lit) => Value::Literal(lit),
+            SpannedKind::Map(map) => Value::Map(entries(map)),
+            SpannedKind::Struct(map) => Value::Struct(entries(map)),
+            SpannedKind::Seq(seq) => {
+                Value::Seq(seq.into_iter().map(SpannedValue::into_value).collect())
+            }
+        }
+    }
+}
+
+/// Parses `s` the way [`from_str`](crate::de::from_str) parses a [`Value`],
+/// including the implicit top level container, recording the span of every
+/// value and entry.
+///
+/// ```
+/// use serde_jacl::spanned::{from_str_spanned, SpannedKind};
+///
+/// let val = from_str_spanned("a : [1 2]").unwrap();
+/// match val.kind {
+///     SpannedKind::Struct(fields) => assert_eq!((4, 9), fields[0].value.span),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn from_str_spanned(s: &str) -> Result<SpannedValue, JaclDeError> {
+    from_str_spanned_with_depth(s, DEFAULT_MAX_DEPTH)
+}
+
+/// Like [`from_str_spanned`], but allows containers to nest `max_depth`
+/// levels deep.
+pub fn from_str_spanned_with_depth(s: &str, max_depth: usize) -> Result<SpannedValue, JaclDeError> {
+    let mut parser = Parser {
+        begin: s,
+        input: s,
+        depth: 0,
+        max_depth,
+    };
+    let val = match Deserializer::from_str(s).implicit_open() {
+        Some(open) => {
+            let kind = parser.contents(open, None)?;
+            SpannedValue {
+                kind,
+                span: (0, s.len()),
+            }
+        }
+        None => {
+            parser.skip();
+            parser.value()?
+        }
+    };
+    parser.skip();
+    if !parser.input.is_empty() {
+        return Err(parser.expected("end of input"));
+    }
+    Ok(val)
+}
+
+struct Parser<'a> {
+    begin: &'a str,
+    input: &'a str,
+    // how many containers we're inside of, and how many we may be
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn offset(&self) -> usize {
+        self.begin.len() - self.input.len()
+    }
+
+    fn expected(&self, expected: &str) -> JaclDeError {
+        JaclDeError::expected_at(self.begin, self.offset(), expected)
+    }
+
+    // skips whitespace and comments
+    fn skip(&mut self) {
+        self.input = many0(alt((
+            parsing::comment::multiline_comment,
+            parsing::comment::eol_comment,
+            parsing::comment::hash_comment,
+            parsing::whitespace,
+        )))(self.input)
+        .map_or(self.input, |(rest, _)| rest);
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        match self.input.strip_prefix(c) {
+            Some(rest) => {
+                self.input = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // parses a value starting at the current position
+    fn value(&mut self) -> Result<SpannedValue, JaclDeError> {
+        let start = self.offset();
+        let kind = match self.input.chars().next() {
+            Some(open @ '[') | Some(open @ '{') | Some(open @ '(') => {
+                self.input = &self.input[1..];
+                let close = match open {
+                    '[' => ']',
+                    '{' => '}',
+                    _ => ')',
+                };
+                self.contents(open, Some(close))?
+            }
+            _ => SpannedKind::Literal(self.literal()?),
+        };
+        Ok(SpannedValue {
+            kind,
+            span: (start, self.offset()),
+        })
+    }
+
+    // the elements or entries of a container up to `close`, or the end of
+    // the input for an implicit container
+    fn contents(&mut self, open: char, close: Option<char>) -> Result<SpannedKind, JaclDeError> {
+        if self.depth >= self.max_depth {
+            let kind = ErrorKind::DepthLimit(self.max_depth);
+            return Err(JaclDeError::at(self.begin, self.offset(), kind));
+        }
+        self.depth += 1;
+        let mut elems = Vec::new();
+        let mut entries = Vec::new();
+        loop {
+            self.skip();
+            match close {
+                Some(close) if self.eat(close) => break,
+                None if self.input.is_empty() => break,
+                _ => {}
+            }
+            if open == '[' {
+                elems.push(self.value()?);
+            } else {
+                entries.push(self.entry(open == '(', close)?);
+            }
+        }
+        self.depth -= 1;
+        Ok(match open {
+            '[' => SpannedKind::Seq(elems),
+            '{' => SpannedKind::Map(entries),
+            _ => SpannedKind::Struct(entries),
+        })
+    }
+
+    fn entry(&mut self, is_struct: bool, close: Option<char>) -> Result<SpannedEntry, JaclDeError> {
+        let start = self.offset();
+        let key = match parsing::string::string(self.input) {
+            Ok((rest, key)) => {
+                self.input = rest;
+                key.into_owned()
+            }
+            Err(_) if is_struct => match parsing::identifier(self.input) {
+                Ok((rest, key)) => {
+                    self.input = rest;
+                    key.to_string()
+                }
+                Err(_) => return Err(self.expected(&expected_key(is_struct, close))),
+            },
+            Err(_) => return Err(self.expected(&expected_key(is_struct, close))),
+        };
+        let key_span = (start, self.offset());
+        self.skip();
+        if !self.eat(':') {
+            return Err(self.expected("`:`"));
+        }
+        self.skip();
+        let value = self.value()?;
+        let span = (start, value.span.1);
+        Ok(SpannedEntry {
+            key,
+            key_span,
+            value,
+            span,
+        })
+    }
+
+    fn literal(&mut self) -> Result<Literal, JaclDeError> {
+        let input = self.input;
+        let (rest, lit) = match input.chars().next() {
+            Some('n') | Some('N') | Some('i') | Some('I')
+                if parsing::literal::float::<f64>(input).is_ok() =>
+            {
+                let (rest, flt) = parsing::literal::float::<f64>(input).unwrap();
+                (rest, Literal::Number(Number::Flt(flt)))
+            }
+            Some('n') if parsing::literal::null(input).is_ok() => {
+                (parsing::literal::null(input).unwrap().0, Literal::Null)
+            }
+            Some('t') | Some('f') if parsing::literal::boolean(input).is_ok() => {
+                let (rest, b) = parsing::literal::boolean(input).unwrap();
+                (rest, Literal::Bool(b))
+            }
+            Some('"') => match parsing::string::string(input) {
+                Ok((rest, s)) => (rest, Literal::String(s.into_owned())),
+                Err(_) => return Err(self.expected("a string")),
+            },
+            Some('-') | Some('+') | Some('0'..='9') => {
+                number(input).ok_or_else(|| self.expected("a number"))?
+            }
+            // a bare word is read as a string
+            _ => match parsing::identifier(input) {
+                Ok((rest, word)) => (rest, Literal::String(word.to_string())),
+                Err(_) => return Err(self.expected("a value")),
+            },
+        };
+        self.input = rest;
+        Ok(lit)
+    }
+}
+
+// integers are signed when they fit, then unsigned, and floats otherwise,
+// like `Deserializer::deserialize_any`
+fn number(input: &str) -> Option<(&str, Literal)> {
+    if let Ok((rest, int)) = parsing::literal::integer::<i64>(input) {
+        if !parsing::literal::is_float_suffix(rest) {
+            return Some((rest, Literal::Number(Number::Int(int))));
+        }
+    } else if let Ok((rest, int)) = parsing::literal::integer::<u64>(input) {
+        if !parsing::literal::is_float_suffix(rest) {
+            return Some((rest, Literal::Number(Number::UInt(int))));
+        }
+    }
+    let (rest, flt) = parsing::literal::float::<f64>(input).ok()?;
+    Some((rest, Literal::Number(Number::Flt(flt))))
+}
+
+fn expected_key(is_struct: bool, close: Option<char>) -> String {
+    let key = if is_struct { "a field name" } else { "a key" };
+    match close {
+        Some(close) => format!("{} or `{}`", key, close),
+        None => key.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::from_str;
+
+    // the text each span covers
+    fn text(s: &str, span: Span) -> &str {
+        &s[span.0..span.1]
+    }
+
+    #[test]
+    fn test_spans() {
+        let s = "name : \"jacl\" // c\nitems : [1 {\"a\" : -2.5} null]\nnested : (x : true)";
+        let val = from_str_spanned(s).unwrap();
+        assert_eq!((0, s.len()), val.span);
+        let fields = match &val.kind {
+            SpannedKind::Struct(fields) => fields,
+            _ => panic!("expected a struct"),
+        };
+        assert_eq!(3, fields.len());
+        assert_eq!("name", text(s, fields[0].key_span));
+        assert_eq!("\"jacl\"", text(s, fields[0].value.span));
+        assert_eq!("name : \"jacl\"", text(s, fields[0].span));
+
+        let items = &fields[1].value;
+        assert_eq!("[1 {\"a\" : -2.5} null]", text(s, items.span));
+        let elems = match &items.kind {
+            SpannedKind::Seq(elems) => elems,
+            _ => panic!("expected a seq"),
+        };
+        assert_eq!("1", text(s, elems[0].span));
+        assert_eq!("{\"a\" : -2.5}", text(s, elems[1].span));
+        assert_eq!("null", text(s, elems[2].span));
+        match &elems[1].kind {
+            SpannedKind::Map(entries) => {
+                assert_eq!("\"a\"", text(s, entries[0].key_span));
+                assert_eq!("-2.5", text(s, entries[0].value.span));
+                assert_eq!("\"a\" : -2.5", text(s, entries[0].span));
+            }
+            _ => panic!("expected a map"),
+        }
+        match &fields[2].value.kind {
+            SpannedKind::Struct(fields) => assert_eq!("true", text(s, fields[0].value.span)),
+            _ => panic!("expected a struct"),
+        }
+
+        assert_eq!(from_str::<Value>(s).unwrap(), val.into_value());
+    }
+
+    #[test]
+    fn test_spanned_values() {
+        let s = "[1 -1 18446744073709551615 1.5 2e3 nan \"a\" b {} ()]";
+        assert_eq!(
+            from_str::<Value>(s).unwrap(),
+            from_str_spanned(s).unwrap().into_value()
+        );
+        let val = from_str_spanned(" 42 ").unwrap();
+        assert_eq!((1, 3), val.span);
+        assert_eq!(Value::int(42), val.into_value());
+    }
+
+    #[test]
+    fn test_spanned_errors() {
+        let err = from_str_spanned("[1 2\n  :]").unwrap_err();
+        assert_eq!((2, 2), (err.line(), err.col()));
+        assert_eq!(Some("expected a value, found `:`"), err.hint());
+        let err = from_str_spanned("{\"a\" 1}").unwrap_err();
+        assert_eq!(Some("expected `:`, found `1`"), err.hint());
+        assert!(from_str_spanned("[1").is_err());
+        assert!(from_str_spanned("[1] ]").is_err());
+    }
+
+    #[test]
+    fn test_spanned_depth() {
+        let err = from_str_spanned(&"[".repeat(200_000)).unwrap_err();
+        assert_eq!(&ErrorKind::DepthLimit(DEFAULT_MAX_DEPTH), err.kind());
+        assert_eq!((1, DEFAULT_MAX_DEPTH + 1), (err.line(), err.col()));
+
+        assert!(from_str_spanned_with_depth("a : [[1]]", 3).is_ok());
+        let err = from_str_spanned_with_depth("a : [[1]]", 2).unwrap_err();
+        assert_eq!(&ErrorKind::DepthLimit(2), err.kind());
+        // the implicit top level counts, as it does for `from_str`
+        let err = from_str_spanned_with_depth("a : 1", 0).unwrap_err();
+        assert_eq!(&ErrorKind::DepthLimit(0), err.kind());
+        assert!(crate::de::from_str_with_depth::<Value>("a : 1", 0).is_err());
+    }
+}
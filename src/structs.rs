@@ -1,25 +1,39 @@
+use serde::de::value::BorrowedStrDeserializer;
 use serde::de::DeserializeOwned;
+use serde::de::DeserializeSeed;
 use serde::de::MapAccess;
 use serde::de::SeqAccess;
+use serde::forward_to_deserialize_any;
 use serde::ser::SerializeMap;
 use serde::ser::SerializeSeq;
-use serde::ser::SerializeStruct;
 use serde::Serialize;
 use serde::{
     de::{self, Visitor},
     Deserialize, Deserializer,
 };
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
 
-use crate::de::from_str;
+use crate::de::decode_hex;
 use crate::de::JaclDeError;
-use crate::ser::to_string;
+use crate::parsing;
+use crate::ser::JaclSerError;
+use crate::ser::STRUCT_NAME;
 
-#[derive(Debug, PartialEq, Clone)]
+/// Numbers compare by value regardless of representation, so `Int(3)` equals
+/// `Flt(3.0)`. To give a total order NaN is equal to itself and greater than
+/// every other number, and `-0.0` equals `0.0`.
+#[derive(Debug, Clone)]
 pub enum Number {
     Int(i64),
+    /// Only used for integers too large to fit in an `i64`.
+    UInt(u64),
     Flt(f64),
 }
 
@@ -27,11 +41,106 @@ impl fmt::Display for Number {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Number::Int(int) => write!(f, "Int({})", int),
+            Number::UInt(int) => write!(f, "UInt({})", int),
             Number::Flt(flt) => write!(f, "Flt({})", flt),
         }
     }
 }
 
+impl Number {
+    // keeps integers that fit in an i64 as Int, so equal values compare equal
+    fn from_u64(value: u64) -> Self {
+        match i64::try_from(value) {
+            Ok(int) => Number::Int(int),
+            Err(_) => Number::UInt(value),
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Number::Int(i) => *i as f64,
+            Number::UInt(u) => *u as f64,
+            Number::Flt(f) => *f,
+        }
+    }
+
+    /// Returns `None` if the number doesn't fit in an `i64` or is a float with
+    /// a fractional part.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Number::Int(i) => Some(*i),
+            Number::UInt(u) => i64::try_from(*u).ok(),
+            // i64::MIN is exactly representable, i64::MAX rounds up to 2^63
+            Number::Flt(f) if f.fract() == 0.0 && *f >= i64::MIN as f64 && *f < i64::MAX as f64 => {
+                Some(*f as i64)
+            }
+            Number::Flt(_) => None,
+        }
+    }
+
+    // exact for Int and UInt, which is all it's used for
+    fn as_i128(&self) -> i128 {
+        match self {
+            Number::Int(i) => *i as i128,
+            Number::UInt(u) => *u as i128,
+            Number::Flt(f) => *f as i128,
+        }
+    }
+}
+
+// compares exactly rather than by casting the integer to a float, which would
+// lose precision above 2^53
+fn cmp_int_flt(int: i128, flt: f64) -> Ordering {
+    if flt.is_nan() {
+        return Ordering::Less;
+    }
+    // every integer we store lies in [-2^63, 2^64)
+    if flt >= u64::MAX as f64 {
+        return Ordering::Less;
+    }
+    if flt < i64::MIN as f64 {
+        return Ordering::Greater;
+    }
+    let trunc = flt.trunc();
+    int.cmp(&(trunc as i128)).then(if flt > trunc {
+        Ordering::Less
+    } else if flt < trunc {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    })
+}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Number::Flt(a), Number::Flt(b)) => match (a.is_nan(), b.is_nan()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => a.partial_cmp(b).unwrap(),
+            },
+            (Number::Flt(f), _) => cmp_int_flt(other.as_i128(), *f).reverse(),
+            (_, Number::Flt(f)) => cmp_int_flt(self.as_i128(), *f),
+            _ => self.as_i128().cmp(&other.as_i128()),
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Number {}
+
 #[derive(Debug)]
 pub struct NumCastErr;
 
@@ -68,6 +177,13 @@ impl<'de> Visitor<'de> for NumberVisitor {
         Ok(Number::Int(value))
     }
 
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Number::from_u64(value))
+    }
+
     fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
     where
         E: de::Error,
@@ -111,6 +227,58 @@ impl Literal {
     pub fn from_flt(f: f64) -> Self {
         Self::Number(Number::Flt(f))
     }
+
+    /// Returns `None` unless this is a number that converts to an `i64`
+    /// exactly, so `2.0` is `Some(2)` but `2.5` is `None`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Literal::Number(num) => num.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` unless this is a number that converts to an `f64`
+    /// exactly. Integers above 2^53 may not.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Literal::Number(Number::Flt(f)) => Some(*f),
+            Literal::Number(num) => {
+                let flt = num.as_f64();
+                // exact when the float is the same number as the integer
+                match cmp_int_flt(num.as_i128(), flt) {
+                    Ordering::Equal => Some(flt),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Literal::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Literal::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Fails unless this is a whole number in the range of a `u32`.
+    pub fn to_u32(&self) -> Result<u32, NumCastErr> {
+        let int = self.as_i64().ok_or(NumCastErr)?;
+        u32::try_from(int).map_err(|_| NumCastErr)
+    }
+
+    /// Fails unless this is a whole number in the range of an `i32`.
+    pub fn to_i32(&self) -> Result<i32, NumCastErr> {
+        let int = self.as_i64().ok_or(NumCastErr)?;
+        i32::try_from(int).map_err(|_| NumCastErr)
+    }
 }
 
 impl<'de> Deserialize<'de> for Literal {
@@ -138,6 +306,13 @@ impl<'de> Visitor<'de> for LiteralVisitor {
         Ok(Literal::Number(Number::Int(value)))
     }
 
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Literal::Number(Number::from_u64(value)))
+    }
+
     fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
     where
         E: de::Error,
@@ -181,6 +356,9 @@ impl<'de> Visitor<'de> for LiteralVisitor {
     }
 }
 
+/// `==` compares numbers by value, like [`Number`], so `Value::int(1)`
+/// equals `Value::flt(1.0)`. Match on the [`Number`] variant to tell how a
+/// number was written.
 #[derive(Debug, PartialEq, Clone)]
 pub enum Value {
     Literal(Literal),
@@ -221,177 +399,1179 @@ impl Value {
         Self::Literal(Literal::Null)
     }
 
-    pub fn convert<T>(&self) -> Result<T, JaclDeError>
-    where
-        T: DeserializeOwned + Serialize,
-    {
-        from_str(to_string(&self).expect("bug! could not serialize Value!"))
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Literal(Literal::String(s)) => Some(s),
+            _ => None,
+        }
     }
-}
 
-impl<'de> Deserialize<'de> for Value {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        deserializer.deserialize_any(ValueVisitor)
+    /// Like [`Literal::as_i64`], `None` unless this is a number that converts
+    /// to an `i64` exactly.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Literal(lit) => lit.as_i64(),
+            _ => None,
+        }
     }
-}
 
-impl Serialize for Value {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
+    /// Like [`Literal::as_f64`], `None` unless this is a number that converts
+    /// to an `f64` exactly.
+    pub fn as_f64(&self) -> Option<f64> {
         match self {
-            Value::Literal(l) => match l {
-                Literal::Number(n) => match n {
-                    Number::Int(v) => serializer.serialize_i64(*v),
-                    Number::Flt(v) => serializer.serialize_f64(*v),
-                },
-                Literal::String(v) => serializer.serialize_str(v),
-                Literal::Bool(v) => serializer.serialize_bool(*v),
-                Literal::Null => serializer.serialize_none(),
-            },
-            Value::Map(m) => {
-                let mut map = serializer.serialize_map(None)?;
-                for (key, value) in m {
-                    map.serialize_entry(key, value)?;
-                }
-                map.end()
-            }
-            Value::Struct(s) => {
-                let mut map = serializer.serialize_map(Some(0))?;
-                for (key, value) in s {
-                    map.serialize_entry(key, value)?;
-                }
-                map.end()
-            }
-            Value::Seq(s) => {
-                let mut seq = serializer.serialize_seq(None)?;
-                for value in s {
-                    seq.serialize_element(value)?;
-                }
-                seq.end()
-            }
+            Value::Literal(lit) => lit.as_f64(),
+            _ => None,
         }
     }
-}
 
-struct ValueVisitor;
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Literal(Literal::Bool(b)) => Some(*b),
+            _ => None,
+        }
+    }
 
-impl<'de> Visitor<'de> for ValueVisitor {
-    type Value = Value;
+    /// Returns the entries of either a `Map` or a `Struct`.
+    pub fn as_map(&self) -> Option<&HashMap<String, Value>> {
+        match self {
+            Value::Map(map) | Value::Struct(map) => Some(map),
+            _ => None,
+        }
+    }
 
-    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a literal, a map, or a sequence")
+    pub fn as_seq(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Seq(seq) => Some(seq),
+            _ => None,
+        }
     }
 
-    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(Value::Literal(Literal::Number(Number::Int(value))))
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Literal(Literal::Null))
     }
 
-    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(Value::Literal(Literal::Number(Number::Flt(v))))
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::Literal(Literal::Number(_)))
     }
 
-    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(Value::Literal(Literal::String(v.to_string())))
+    pub fn is_string(&self) -> bool {
+        matches!(self, Value::Literal(Literal::String(_)))
     }
 
-    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(Value::Literal(Literal::String(v)))
+    pub fn is_bool(&self) -> bool {
+        matches!(self, Value::Literal(Literal::Bool(_)))
     }
 
-    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(Value::Literal(Literal::Bool(v)))
+    pub fn is_map(&self) -> bool {
+        matches!(self, Value::Map(_))
     }
 
-    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-    where
-        A: SeqAccess<'de>,
-    {
-        let mut vec = Vec::new();
-        while let Some(elem) = seq.next_element()? {
-            vec.push(elem);
+    pub fn is_struct(&self) -> bool {
+        matches!(self, Value::Struct(_))
+    }
+
+    pub fn is_seq(&self) -> bool {
+        matches!(self, Value::Seq(_))
+    }
+
+    /// Looks up `key` in a `Map` or `Struct`. Unlike indexing, a missing key
+    /// is `None` rather than null.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_map()?.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        match self {
+            Value::Map(map) | Value::Struct(map) => map.get_mut(key),
+            _ => None,
         }
-        Ok(Value::Seq(vec))
     }
 
-    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    /// Removes `key` from a `Map` or `Struct` and returns its value.
+    pub fn take(&mut self, key: &str) -> Option<Value> {
+        match self {
+            Value::Map(map) | Value::Struct(map) => map.remove(key),
+            _ => None,
+        }
+    }
+
+    /// Returns the element at `index` in a `Seq`.
+    pub fn get_index(&self, index: usize) -> Option<&Value> {
+        self.as_seq()?.get(index)
+    }
+
+    /// Merges `other` into this value, with `other` taking precedence:
+    /// - if both are a `Map` or `Struct`, they are merged key by key,
+    ///   recursively, and this value keeps its variant
+    /// - otherwise `other` replaces this value wholesale, so scalars and
+    ///   sequences are overridden rather than combined
+    pub fn merge(&mut self, other: Value) {
+        match (self, other) {
+            (Value::Map(map), Value::Map(other))
+            | (Value::Map(map), Value::Struct(other))
+            | (Value::Struct(map), Value::Map(other))
+            | (Value::Struct(map), Value::Struct(other)) => {
+                for (key, value) in other {
+                    match map.get_mut(&key) {
+                        Some(existing) => existing.merge(value),
+                        None => {
+                            map.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (this, other) => *this = other,
+        }
+    }
+
+    /// Looks up a value by a slash separated path like `/server/ports/0`.
+    /// Each segment is a `Map` or `Struct` key, or an index into a `Seq`.
+    /// As in JSON pointers, `~1` and `~0` escape `/` and `~` in keys, and the
+    /// empty path refers to this value.
+    pub fn pointer(&self, path: &str) -> Option<&Value> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        if !path.starts_with('/') {
+            return None;
+        }
+        let mut target = self;
+        for segment in path[1..].split('/') {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            target = match target {
+                Value::Map(map) | Value::Struct(map) => map.get(&segment)?,
+                Value::Seq(seq) => seq.get(segment.parse::<usize>().ok()?)?,
+                Value::Literal(_) => return None,
+            };
+        }
+        Some(target)
+    }
+
+    /// Like [`pointer`](Value::pointer), but returns a mutable reference.
+    pub fn pointer_mut(&mut self, path: &str) -> Option<&mut Value> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        if !path.starts_with('/') {
+            return None;
+        }
+        let mut target = self;
+        for segment in path[1..].split('/') {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            target = match target {
+                Value::Map(map) | Value::Struct(map) => map.get_mut(&segment)?,
+                Value::Seq(seq) => seq.get_mut(segment.parse::<usize>().ok()?)?,
+                Value::Literal(_) => return None,
+            };
+        }
+        Some(target)
+    }
+
+    pub fn convert<T>(&self) -> Result<T, JaclDeError>
     where
-        A: MapAccess<'de>,
+        T: DeserializeOwned + Serialize,
     {
-        let mut m = HashMap::new();
-        while let Some((key, value)) = map.next_entry()? {
-            m.insert(key, value);
-        }
-        if map.size_hint().is_none() {
-            Ok(Value::Map(m))
+        T::deserialize(ValueDeserializer::new(self))
+    }
+
+    /// Serializes this value as JACL, the inverse of `str::parse`.
+    pub fn to_jacl_string(&self) -> Result<String, JaclSerError> {
+        crate::ser::to_string(self)
+    }
+
+    /// Writes this value as JACL with each entry on its own line, indented by
+    /// `indent` spaces per level. Sequences of literals stay on one line, and
+    /// keys are sorted so that the output doesn't change from run to run.
+    ///
+    /// ```
+    /// use serde_jacl::structs::Value;
+    ///
+    /// let value: Value = "name : \"jacl\" ports : [80 443]".parse().unwrap();
+    /// assert_eq!(
+    ///     "(\n  name : \"jacl\"\n  ports : [80 443]\n)",
+    ///     value.to_pretty_jacl(2)
+    /// );
+    /// ```
+    pub fn to_pretty_jacl(&self, indent: usize) -> String {
+        let mut out = String::new();
+        write_pretty(self, indent, 0, &mut out);
+        out
+    }
+
+    /// Checks that this value has the shape described by `schema`, reporting
+    /// every mismatch rather than only the first.
+    pub fn validate(&self, schema: &Schema) -> Result<(), ValidationError> {
+        let mut mismatches = Vec::new();
+        validate(self, schema, &mut String::new(), &mut mismatches);
+        if mismatches.is_empty() {
+            Ok(())
         } else {
-            Ok(Value::Struct(m))
+            Err(ValidationError { mismatches })
         }
     }
 
-    fn visit_none<E>(self) -> Result<Self::Value, E>
+    /// Calls `f` with this value and then with every value nested in it,
+    /// each along with the path to it from this value. Parents are visited
+    /// before their children, and the entries of a map in no particular order.
+    pub fn for_each<F>(&self, mut f: F)
     where
-        E: de::Error,
+        F: FnMut(&[PathSegment], &Value),
     {
-        Ok(Value::Literal(Literal::Null))
+        fn walk<F>(value: &Value, path: &mut Vec<PathSegment>, f: &mut F)
+        where
+            F: FnMut(&[PathSegment], &Value),
+        {
+            f(path, value);
+            match value {
+                Value::Map(map) | Value::Struct(map) => {
+                    for (key, value) in map {
+                        path.push(PathSegment::Key(key.clone()));
+                        walk(value, path, f);
+                        path.pop();
+                    }
+                }
+                Value::Seq(seq) => {
+                    for (i, value) in seq.iter().enumerate() {
+                        path.push(PathSegment::Index(i));
+                        walk(value, path, f);
+                        path.pop();
+                    }
+                }
+                Value::Literal(_) => {}
+            }
+        }
+        walk(self, &mut Vec::new(), &mut f)
     }
 
-    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    /// Like [`for_each`](Value::for_each), but `f` may change the values it's
+    /// given. A value's children are visited after `f` has been called on
+    /// it, so a value that `f` replaces has the new value's children visited.
+    pub fn for_each_mut<F>(&mut self, mut f: F)
     where
-        D: Deserializer<'de>,
+        F: FnMut(&[PathSegment], &mut Value),
     {
-        deserializer.deserialize_any(self)
+        fn walk<F>(value: &mut Value, path: &mut Vec<PathSegment>, f: &mut F)
+        where
+            F: FnMut(&[PathSegment], &mut Value),
+        {
+            f(path, value);
+            match value {
+                Value::Map(map) | Value::Struct(map) => {
+                    for (key, value) in map {
+                        path.push(PathSegment::Key(key.clone()));
+                        walk(value, path, f);
+                        path.pop();
+                    }
+                }
+                Value::Seq(seq) => {
+                    for (i, value) in seq.iter_mut().enumerate() {
+                        path.push(PathSegment::Index(i));
+                        walk(value, path, f);
+                        path.pop();
+                    }
+                }
+                Value::Literal(_) => {}
+            }
+        }
+        walk(self, &mut Vec::new(), &mut f)
     }
-}
-
-mod tests {
-    use super::*;
 
-    #[test]
-    fn test_number() {
-        let int = Number::Int(1);
-        let flt = Number::Flt(1.75);
-        // check parsing
-        assert_eq!(int, from_str::<Number>("1").unwrap());
-        assert_eq!(flt, from_str::<Number>("1.75").unwrap());
+    // names this value's type for validation errors
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Literal(Literal::Null) => "null",
+            Value::Literal(Literal::Bool(_)) => "a bool",
+            Value::Literal(Literal::Number(_)) => "a number",
+            Value::Literal(Literal::String(_)) => "a string",
+            Value::Map(_) => "a map",
+            Value::Struct(_) => "a struct",
+            Value::Seq(_) => "a sequence",
+        }
     }
+}
 
-    #[test]
-    fn test_literal() {
-        let int = Literal::from_int(1);
-        let flt = Literal::from_flt(1.75);
-        let b = Literal::from_bool(true);
-        let s = Literal::from_string("hello world");
-        let null = Literal::Null;
+/// One step along the path to a nested value, for [`Value::for_each`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum PathSegment {
+    /// An entry of a `Map` or `Struct`.
+    Key(String),
+    /// An element of a `Seq`.
+    Index(usize),
+}
 
-        assert_eq!(b, from_str("true").unwrap());
-        assert_eq!(s, from_str(r#"  "hello world"   "#).unwrap());
-        assert_eq!(int, from_str("1").unwrap());
-        assert_eq!(flt, from_str("1.75").unwrap());
-        assert_eq!(null, from_str("null").unwrap());
+/// The expected shape of a [`Value`], for [`Value::validate`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum Schema {
+    AnyNumber,
+    String,
+    Bool,
+    /// A sequence whose elements all match the schema.
+    Seq(Box<Schema>),
+    /// A `Map` or `Struct` that has each of these keys. Keys that aren't
+    /// listed are allowed.
+    Map(HashMap<String, Schema>),
+    /// Either null or a value matching the schema. As a map entry, the key
+    /// may also be left out.
+    Optional(Box<Schema>),
+}
+
+impl Schema {
+    fn name(&self) -> &'static str {
+        match self {
+            Schema::AnyNumber => "a number",
+            Schema::String => "a string",
+            Schema::Bool => "a bool",
+            Schema::Seq(_) => "a sequence",
+            Schema::Map(_) => "a map",
+            Schema::Optional(schema) => schema.name(),
+        }
     }
+}
 
-    #[test]
-    fn test_value() {
+/// Where a value doesn't match its schema. `path` is in the form taken by
+/// [`Value::pointer`], e.g. `/server/ports/0`, and is empty for the value
+/// that was validated. It's displayed as `(root)` then.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mismatch {
+    pub path: String,
+    pub kind: MismatchKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MismatchKind {
+    /// The value is of the wrong type. Both are described like "a number".
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// A required key is missing.
+    Missing,
+}
+
+/// Every mismatch found by [`Value::validate`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct ValidationError {
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, mismatch) in self.mismatches.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let path = if mismatch.path.is_empty() {
+                "(root)"
+            } else {
+                &mismatch.path
+            };
+            match mismatch.kind {
+                MismatchKind::WrongType { expected, found } => {
+                    write!(f, "{}: expected {}, found {}", path, expected, found)?
+                }
+                MismatchKind::Missing => write!(f, "{}: missing", path)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+// `path` is the pointer to `value`, extended in place as the trees are walked
+fn validate(value: &Value, schema: &Schema, path: &mut String, mismatches: &mut Vec<Mismatch>) {
+    let matches = match (schema, value) {
+        (Schema::Optional(_), Value::Literal(Literal::Null)) => true,
+        (Schema::Optional(schema), value) => return validate(value, schema, path, mismatches),
+        (Schema::AnyNumber, value) => value.is_number(),
+        (Schema::String, value) => value.is_string(),
+        (Schema::Bool, value) => value.is_bool(),
+        (Schema::Seq(schema), Value::Seq(seq)) => {
+            for (i, elem) in seq.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/{}", i));
+                validate(elem, schema, path, mismatches);
+                path.truncate(len);
+            }
+            true
+        }
+        (Schema::Map(schemas), Value::Map(map)) | (Schema::Map(schemas), Value::Struct(map)) => {
+            for (key, schema) in schemas {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                match map.get(key) {
+                    Some(value) => validate(value, schema, path, mismatches),
+                    None if matches!(schema, Schema::Optional(_)) => {}
+                    None => mismatches.push(Mismatch {
+                        path: path.clone(),
+                        kind: MismatchKind::Missing,
+                    }),
+                }
+                path.truncate(len);
+            }
+            true
+        }
+        _ => false,
+    };
+    if !matches {
+        mismatches.push(Mismatch {
+            path: path.clone(),
+            kind: MismatchKind::WrongType {
+                expected: schema.name(),
+                found: value.type_name(),
+            },
+        });
+    }
+}
+
+fn write_pretty(value: &Value, indent: usize, depth: usize, out: &mut String) {
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&" ".repeat(indent * depth));
+    };
+    let (entries, open, close) = match value {
+        Value::Literal(lit) => return write_literal(lit, out),
+        Value::Seq(seq) if seq.iter().all(|val| matches!(val, Value::Literal(_))) => {
+            out.push('[');
+            for (i, val) in seq.iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                write_pretty(val, indent, depth + 1, out);
+            }
+            out.push(']');
+            return;
+        }
+        Value::Seq(seq) => {
+            out.push('[');
+            for val in seq {
+                newline(out, depth + 1);
+                write_pretty(val, indent, depth + 1, out);
+            }
+            newline(out, depth);
+            out.push(']');
+            return;
+        }
+        Value::Map(map) => (map, '{', '}'),
+        Value::Struct(map) => (map, '(', ')'),
+    };
+    out.push(open);
+    let mut keys: Vec<&String> = entries.keys().collect();
+    keys.sort();
+    for key in &keys {
+        newline(out, depth + 1);
+        // map keys are always strings, struct keys only when they have to be
+        match parsing::identifier(key) {
+            Ok(("", _)) if open == '(' => out.push_str(key),
+            _ => write_literal(&Literal::String(key.to_string()), out),
+        }
+        out.push_str(" : ");
+        write_pretty(&entries[*key], indent, depth + 1, out);
+    }
+    if !keys.is_empty() {
+        newline(out, depth);
+    }
+    out.push(close);
+}
+
+fn write_literal(lit: &Literal, out: &mut String) {
+    match lit {
+        Literal::Number(Number::Int(int)) => out.push_str(&int.to_string()),
+        Literal::Number(Number::UInt(int)) => out.push_str(&int.to_string()),
+        // `{:?}` keeps the `.0` of whole floats, so they read back as floats
+        Literal::Number(Number::Flt(flt)) => out.push_str(&format!("{:?}", flt)),
+        Literal::String(s) => {
+            out.push('"');
+            out.push_str(&escape8259::escape(s));
+            out.push('"');
+        }
+        Literal::Bool(b) => out.push_str(&b.to_string()),
+        Literal::Null => out.push_str("null"),
+    }
+}
+
+impl FromStr for Value {
+    type Err = JaclDeError;
+
+    fn from_str(s: &str) -> Result<Self, JaclDeError> {
+        crate::de::from_str(s)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Value::int(i)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Value::flt(f)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::bool(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::string(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::string(s)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(seq: Vec<Value>) -> Self {
+        Value::Seq(seq)
+    }
+}
+
+impl From<HashMap<String, Value>> for Value {
+    fn from(map: HashMap<String, Value>) -> Self {
+        Value::Map(map)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(opt: Option<T>) -> Self {
+        match opt {
+            Some(v) => v.into(),
+            None => Value::null(),
+        }
+    }
+}
+
+impl FromIterator<Value> for Value {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Value::Seq(iter.into_iter().collect())
+    }
+}
+
+/// Both `Map` and `Struct` become JSON objects. Non-finite floats have no
+/// JSON representation and become null.
+#[cfg(feature = "json")]
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Literal(Literal::Number(Number::Int(i))) => i.into(),
+            Value::Literal(Literal::Number(Number::UInt(u))) => u.into(),
+            Value::Literal(Literal::Number(Number::Flt(f))) => serde_json::Number::from_f64(f)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Value::Literal(Literal::String(s)) => s.into(),
+            Value::Literal(Literal::Bool(b)) => b.into(),
+            Value::Literal(Literal::Null) => serde_json::Value::Null,
+            Value::Map(map) | Value::Struct(map) => {
+                serde_json::Value::Object(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+            Value::Seq(seq) => serde_json::Value::Array(seq.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+/// JSON objects always become a `Map`.
+#[cfg(feature = "json")]
+impl TryFrom<serde_json::Value> for Value {
+    type Error = NumCastErr;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, NumCastErr> {
+        Ok(match value {
+            serde_json::Value::Null => Value::null(),
+            serde_json::Value::Bool(b) => Value::bool(b),
+            serde_json::Value::Number(n) => {
+                let num = if let Some(i) = n.as_i64() {
+                    Number::Int(i)
+                } else if let Some(u) = n.as_u64() {
+                    Number::UInt(u)
+                } else {
+                    Number::Flt(n.as_f64().ok_or(NumCastErr)?)
+                };
+                Value::Literal(Literal::Number(num))
+            }
+            serde_json::Value::String(s) => Value::string(s),
+            serde_json::Value::Array(seq) => Value::Seq(
+                seq.into_iter()
+                    .map(Value::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            serde_json::Value::Object(map) => Value::Map(
+                map.into_iter()
+                    .map(|(k, v)| Ok((k, Value::try_from(v)?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+}
+
+// returned by reference when indexing misses
+static NULL: Value = Value::Literal(Literal::Null);
+
+impl Index<&str> for Value {
+    type Output = Value;
+
+    /// Looks up `key` in a `Map` or `Struct`. Returns null if the key is
+    /// missing or this isn't a `Map` or `Struct`.
+    fn index(&self, key: &str) -> &Value {
+        match self {
+            Value::Map(map) | Value::Struct(map) => map.get(key).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+impl IndexMut<&str> for Value {
+    /// Looks up `key` in a `Map` or `Struct`, inserting null if it's missing.
+    /// A null value is first turned into an empty `Map`.
+    ///
+    /// # Panics
+    /// Panics if this isn't a `Map`, `Struct` or null.
+    fn index_mut(&mut self, key: &str) -> &mut Value {
+        if self.is_null() {
+            *self = Value::Map(HashMap::new());
+        }
+        match self {
+            Value::Map(map) | Value::Struct(map) => {
+                map.entry(key.to_string()).or_insert_with(Value::null)
+            }
+            _ => panic!("cannot index into {} with key {:?}", self, key),
+        }
+    }
+}
+
+impl Index<usize> for Value {
+    type Output = Value;
+
+    /// Returns the element at `index` in a `Seq`, or null if it's out of
+    /// bounds or this isn't a `Seq`.
+    fn index(&self, index: usize) -> &Value {
+        match self {
+            Value::Seq(seq) => seq.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+impl IndexMut<usize> for Value {
+    /// Returns the element at `index` in a `Seq`.
+    ///
+    /// # Panics
+    /// Panics if this isn't a `Seq` or `index` is out of bounds.
+    fn index_mut(&mut self, index: usize) -> &mut Value {
+        match self {
+            Value::Seq(seq) => {
+                let len = seq.len();
+                seq.get_mut(index).unwrap_or_else(|| {
+                    panic!("index {} out of bounds for Seq of length {}", index, len)
+                })
+            }
+            _ => panic!("cannot index into {} with {}", self, index),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+// the fields of a `Value::Struct`, written as a map
+struct StructFields<'a>(&'a HashMap<String, Value>);
+
+impl Serialize for StructFields<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(0))?;
+        for (key, value) in self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Value::Literal(l) => match l {
+                Literal::Number(n) => match n {
+                    Number::Int(v) => serializer.serialize_i64(*v),
+                    Number::UInt(v) => serializer.serialize_u64(*v),
+                    Number::Flt(v) => serializer.serialize_f64(*v),
+                },
+                Literal::String(v) => serializer.serialize_str(v),
+                Literal::Bool(v) => serializer.serialize_bool(*v),
+                Literal::Null => serializer.serialize_none(),
+            },
+            Value::Map(m) => {
+                let mut map = serializer.serialize_map(None)?;
+                for (key, value) in m {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+            Value::Struct(s) => serializer.serialize_newtype_struct(STRUCT_NAME, &StructFields(s)),
+            Value::Seq(s) => {
+                let mut seq = serializer.serialize_seq(None)?;
+                for value in s {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a literal, a map, or a sequence")
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Literal(Literal::Number(Number::Int(value))))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Literal(Literal::Number(Number::from_u64(value))))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Literal(Literal::Number(Number::Flt(v))))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Literal(Literal::String(v.to_string())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Literal(Literal::String(v)))
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Literal(Literal::Bool(v)))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut vec = Vec::new();
+        while let Some(elem) = seq.next_element()? {
+            vec.push(elem);
+        }
+        Ok(Value::Seq(vec))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut m = HashMap::new();
+        while let Some((key, value)) = map.next_entry()? {
+            m.insert(key, value);
+        }
+        if map.size_hint().is_none() {
+            Ok(Value::Map(m))
+        } else {
+            Ok(Value::Struct(m))
+        }
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Literal(Literal::Null))
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+/// Deserializes straight from a borrowed `Value`, without going through text.
+pub struct ValueDeserializer<'a> {
+    value: &'a Value,
+}
+
+impl<'a> ValueDeserializer<'a> {
+    pub fn new(value: &'a Value) -> Self {
+        ValueDeserializer { value }
+    }
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = JaclDeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Literal(Literal::Number(Number::Int(v))) => visitor.visit_i64(*v),
+            Value::Literal(Literal::Number(Number::UInt(v))) => visitor.visit_u64(*v),
+            Value::Literal(Literal::Number(Number::Flt(v))) => visitor.visit_f64(*v),
+            Value::Literal(Literal::String(v)) => visitor.visit_borrowed_str(v),
+            Value::Literal(Literal::Bool(v)) => visitor.visit_bool(*v),
+            Value::Literal(Literal::Null) => visitor.visit_none(),
+            Value::Map(map) => visitor.visit_map(MapRefAccess::new(map, DataType::HashMap)),
+            Value::Struct(map) => visitor.visit_map(MapRefAccess::new(map, DataType::Struct)),
+            Value::Seq(seq) => visitor.visit_seq(SeqRefAccess { iter: seq.iter() }),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Literal(Literal::Null) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    // like the text deserializer, unit is either null or ()
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Literal(Literal::Null) => visitor.visit_unit(),
+            Value::Struct(map) if map.is_empty() => visitor.visit_unit(),
+            _ => Err(de::Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // bytes are either a sequence of u8 or a hex string
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Literal(Literal::String(s)) => match decode_hex(s) {
+                Some(bytes) => visitor.visit_byte_buf(bytes),
+                None => Err(de::Error::invalid_value(self.unexpected(), &visitor)),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    // a bare word like `Red` is read into a Value as a string, which can only
+    // be a unit variant
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, JaclDeError>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Literal(Literal::String(s)) => {
+                visitor.visit_enum(BorrowedStrDeserializer::new(s))
+            }
+            _ => Err(de::Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        seq tuple tuple_struct map struct identifier
+    }
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn unexpected(&self) -> de::Unexpected<'a> {
+        match self.value {
+            Value::Literal(Literal::Number(Number::Int(v))) => de::Unexpected::Signed(*v),
+            Value::Literal(Literal::Number(Number::UInt(v))) => de::Unexpected::Unsigned(*v),
+            Value::Literal(Literal::Number(Number::Flt(v))) => de::Unexpected::Float(*v),
+            Value::Literal(Literal::String(v)) => de::Unexpected::Str(v),
+            Value::Literal(Literal::Bool(v)) => de::Unexpected::Bool(*v),
+            Value::Literal(Literal::Null) => de::Unexpected::Option,
+            Value::Map(_) | Value::Struct(_) => de::Unexpected::Map,
+            Value::Seq(_) => de::Unexpected::Seq,
+        }
+    }
+}
+
+struct SeqRefAccess<'a> {
+    iter: std::slice::Iter<'a, Value>,
+}
+
+impl<'de> SeqAccess<'de> for SeqRefAccess<'de> {
+    type Error = JaclDeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, JaclDeError>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(value) => seed.deserialize(ValueDeserializer::new(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+enum DataType {
+    HashMap,
+    Struct,
+}
+
+struct MapRefAccess<'a> {
+    iter: std::collections::hash_map::Iter<'a, String, Value>,
+    value: Option<&'a Value>,
+    datatype: DataType,
+}
+
+impl<'a> MapRefAccess<'a> {
+    fn new(map: &'a HashMap<String, Value>, datatype: DataType) -> Self {
+        MapRefAccess {
+            iter: map.iter(),
+            value: None,
+            datatype,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for MapRefAccess<'de> {
+    type Error = JaclDeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, JaclDeError>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(BorrowedStrDeserializer::new(key))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, JaclDeError>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(ValueDeserializer::new(value)),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+
+    // same convention as the text deserializer: Some(0) marks a struct
+    fn size_hint(&self) -> Option<usize> {
+        match self.datatype {
+            DataType::Struct => Some(0),
+            DataType::HashMap => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::from_str;
+    use crate::ser::to_string;
+    use serde::Deserialize;
+
+    #[test]
+    fn test_number() {
+        let int = Number::Int(1);
+        let flt = Number::Flt(1.75);
+        // check parsing
+        assert_eq!(int, from_str::<Number>("1").unwrap());
+        assert_eq!(flt, from_str::<Number>("1.75").unwrap());
+        // equality ignores the representation, so check it separately
+        assert!(matches!(from_str::<Number>("1").unwrap(), Number::Int(1)));
+        assert!(matches!(from_str::<Number>("1.0").unwrap(), Number::Flt(_)));
+        assert!(matches!(
+            from_str::<Number>("1.75").unwrap(),
+            Number::Flt(_)
+        ));
+    }
+
+    #[test]
+    fn test_number_ordering() {
+        use std::collections::BTreeSet;
+        assert_eq!(Number::Int(3), Number::Flt(3.0));
+        assert!(Number::UInt(u64::MAX) < Number::Flt(u64::MAX as f64));
+        assert!(Number::Int(3) < Number::Flt(3.5));
+        assert!(Number::Flt(2.5) < Number::Int(3));
+        assert!(Number::Flt(-3.5) < Number::Int(-3));
+        assert!(Number::Int(i64::MAX) < Number::UInt(i64::MAX as u64 + 1));
+        assert!(Number::Int(i64::MAX) < Number::Flt(i64::MAX as f64));
+        assert_eq!(Number::Flt(0.0), Number::Flt(-0.0));
+        assert_eq!(Number::Flt(f64::NAN), Number::Flt(f64::NAN));
+        assert!(Number::Flt(f64::INFINITY) < Number::Flt(f64::NAN));
+        assert!(Number::UInt(u64::MAX) < Number::Flt(f64::NAN));
+
+        let set: BTreeSet<Number> = vec![
+            Number::Flt(2.5),
+            Number::Int(3),
+            Number::Flt(3.0),
+            Number::Int(-1),
+            Number::Flt(f64::NAN),
+        ]
+        .into_iter()
+        .collect();
+        let sorted: Vec<Number> = set.into_iter().collect();
+        assert_eq!(4, sorted.len());
+        assert_eq!(
+            vec![
+                Number::Int(-1),
+                Number::Flt(2.5),
+                Number::Int(3),
+                Number::Flt(f64::NAN)
+            ],
+            sorted
+        );
+    }
+
+    #[test]
+    fn test_number_casts() {
+        assert_eq!(3.0, Number::Int(3).as_f64());
+        assert_eq!(2.5, Number::Flt(2.5).as_f64());
+        assert_eq!(Some(3), Number::Flt(3.0).as_i64());
+        assert_eq!(Some(-3), Number::Int(-3).as_i64());
+        assert_eq!(None, Number::Flt(3.5).as_i64());
+        assert_eq!(None, Number::Flt(f64::NAN).as_i64());
+        assert_eq!(None, Number::Flt(1e19).as_i64());
+        assert_eq!(Some(i64::MIN), Number::Flt(i64::MIN as f64).as_i64());
+        assert_eq!(None, Number::UInt(u64::MAX).as_i64());
+    }
+
+    #[test]
+    fn test_u64() {
+        let max = Value::Literal(Literal::Number(Number::UInt(u64::MAX)));
+        assert_eq!(u64::MAX, from_str::<u64>("18446744073709551615").unwrap());
+        assert_eq!(max, from_str::<Value>("18446744073709551615").unwrap());
+        assert_eq!("18446744073709551615", to_string(&max).unwrap());
+        assert_eq!(max, from_str::<Value>(&to_string(&max).unwrap()).unwrap());
+        assert_eq!(Number::Int(1), from_str::<Number>("1").unwrap());
+        assert_eq!(
+            Number::UInt(i64::MAX as u64 + 1),
+            from_str::<Number>("9223372036854775808").unwrap()
+        );
+        assert!(matches!(
+            from_str::<Number>("9223372036854775808").unwrap(),
+            Number::UInt(_)
+        ));
+        assert!(matches!(
+            from_str::<Value>("18446744073709551615").unwrap(),
+            Value::Literal(Literal::Number(Number::UInt(u64::MAX)))
+        ));
+    }
+
+    #[test]
+    fn test_literal() {
+        let int = Literal::from_int(1);
+        let flt = Literal::from_flt(1.75);
+        let b = Literal::from_bool(true);
+        let s = Literal::from_string("hello world");
+        let null = Literal::Null;
+
+        assert_eq!(b, from_str("true").unwrap());
+        assert_eq!(s, from_str(r#"  "hello world"   "#).unwrap());
+        assert_eq!(int, from_str("1").unwrap());
+        assert_eq!(flt, from_str("1.75").unwrap());
+        assert_eq!(null, from_str("null").unwrap());
+        assert!(matches!(
+            from_str("1").unwrap(),
+            Literal::Number(Number::Int(1))
+        ));
+        assert!(matches!(
+            from_str("1.75").unwrap(),
+            Literal::Number(Number::Flt(_))
+        ));
+    }
+
+    #[test]
+    fn test_value() {
         let val = Value::Seq(vec![
             Value::bool(true),
             Value::flt(1.0),
@@ -419,6 +1599,15 @@ mod tests {
             )
             .unwrap()
         );
+        // `==` is numeric, so check that `1.0` stayed a float
+        assert!(matches!(
+            from_str::<Value>("[true 1.0]").unwrap()[1],
+            Value::Literal(Literal::Number(Number::Flt(_)))
+        ));
+        assert!(matches!(
+            from_str::<Value>("[true 1]").unwrap()[1],
+            Value::Literal(Literal::Number(Number::Int(1)))
+        ));
 
         let map = Value::Map(HashMap::from([
             ("key_0".into(), Value::null()),
@@ -439,9 +1628,546 @@ mod tests {
 
     #[test]
     fn test_value_struct() {
+        let val = Value::Struct(HashMap::from([("a".into(), Value::int(0))]));
+        assert_eq!(val, from_str("(\"a\" : 0)").unwrap());
+    }
+
+    #[test]
+    fn test_struct_round_trip() {
         let val = Value::Struct(HashMap::from([
             ("a".into(), Value::int(0)),
+            (
+                "b".into(),
+                Value::Map(HashMap::from([("c".into(), Value::null())])),
+            ),
+        ]));
+        let out = to_string(&val).unwrap();
+        assert!(out.starts_with('('));
+        assert_eq!(val, from_str::<Value>(&out).unwrap());
+        let val = Value::Seq(vec![val, Value::Map(HashMap::new())]);
+        assert_eq!(val, from_str::<Value>(&to_string(&val).unwrap()).unwrap());
+        assert_eq!(
+            "[() {}]",
+            to_string(&Value::Seq(vec![
+                Value::Struct(HashMap::new()),
+                Value::Map(HashMap::new())
+            ]))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_for_each() {
+        use std::collections::HashSet;
+        use PathSegment::{Index, Key};
+
+        let val: Value = from_str(r#"name : "a" db : (password : "p" ports : [1 2])"#).unwrap();
+        let mut paths = HashSet::new();
+        val.for_each(|path, _| {
+            paths.insert(path.to_vec());
+        });
+        let key = |k: &str| Key(k.to_string());
+        let expected = HashSet::from([
+            vec![],
+            vec![key("name")],
+            vec![key("db")],
+            vec![key("db"), key("password")],
+            vec![key("db"), key("ports")],
+            vec![key("db"), key("ports"), Index(0)],
+            vec![key("db"), key("ports"), Index(1)],
+        ]);
+        assert_eq!(expected, paths);
+
+        let mut count = 0;
+        val.for_each(|path, value| {
+            if path.is_empty() {
+                assert_eq!(&val, value);
+            }
+            count += 1;
+        });
+        assert_eq!(7, count);
+    }
+
+    #[test]
+    fn test_for_each_mut() {
+        let mut val: Value =
+            from_str(r#"name : "a" db : (password : "p" ports : [1 2]) password : (x : 1)"#)
+                .unwrap();
+        val.for_each_mut(|path, value| {
+            if path.last() == Some(&PathSegment::Key("password".to_string())) {
+                *value = Value::string("***");
+            }
+            if let Value::Literal(Literal::Number(Number::Int(i))) = value {
+                *i *= 10;
+            }
+        });
+        let expected: Value =
+            from_str(r#"name : "a" db : (password : "***" ports : [10 20]) password : "***""#)
+                .unwrap();
+        assert_eq!(expected, val);
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = Schema::Map(HashMap::from([
+            ("name".to_string(), Schema::String),
+            (
+                "ports".to_string(),
+                Schema::Seq(Box::new(Schema::AnyNumber)),
+            ),
+            (
+                "tls".to_string(),
+                Schema::Optional(Box::new(Schema::Map(HashMap::from([(
+                    "cert".to_string(),
+                    Schema::String,
+                )])))),
+            ),
+            (
+                "debug".to_string(),
+                Schema::Optional(Box::new(Schema::Bool)),
+            ),
+        ]));
+
+        let val: Value =
+            from_str(r#"name : "a" ports : [80 443] tls : (cert : "c") extra : 1"#).unwrap();
+        assert_eq!(Ok(()), val.validate(&schema));
+        let val: Value = from_str(r#"name : "a" ports : [] tls : null"#).unwrap();
+        assert_eq!(Ok(()), val.validate(&schema));
+
+        let val: Value =
+            from_str(r#"name : 1 ports : [80 "443"] tls : (key : "k") debug : "yes""#).unwrap();
+        let mut mismatches = val.validate(&schema).unwrap_err().mismatches;
+        mismatches.sort_by(|a, b| a.path.cmp(&b.path));
+        let wrong_type = |path: &str, expected, found| Mismatch {
+            path: path.to_string(),
+            kind: MismatchKind::WrongType { expected, found },
+        };
+        assert_eq!(
+            vec![
+                wrong_type("/debug", "a bool", "a string"),
+                wrong_type("/name", "a string", "a number"),
+                wrong_type("/ports/1", "a number", "a string"),
+                Mismatch {
+                    path: "/tls/cert".to_string(),
+                    kind: MismatchKind::Missing,
+                },
+            ],
+            mismatches
+        );
+
+        let err = Value::int(1).validate(&schema).unwrap_err();
+        assert_eq!("(root): expected a map, found a number", err.to_string());
+        assert_eq!(
+            Some(&Value::int(1)),
+            Value::int(1).pointer(&err.mismatches[0].path)
+        );
+        let val: Value = from_str(r#"name : 1 ports : []"#).unwrap();
+        let err = val.validate(&schema).unwrap_err();
+        assert_eq!("/name: expected a string, found a number", err.to_string());
+    }
+
+    #[test]
+    fn test_convert() {
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct Config {
+            name: String,
+            sizes: Vec<u32>,
+            scale: f64,
+            parent: Option<Box<Config>>,
+        }
+        let val: Value = from_str(r#"name : "a" sizes : [1 2] scale : 1 parent : null"#).unwrap();
+        let expected = Config {
+            name: "a".into(),
+            sizes: vec![1, 2],
+            scale: 1.0,
+            parent: None,
+        };
+        assert_eq!(expected, val.convert().unwrap());
+        assert!(val.convert::<Vec<u32>>().is_err());
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        enum Color {
+            Red,
+            Blue,
+        }
+        let val: Value = from_str("[Red Blue]").unwrap();
+        assert_eq!(
+            vec![Color::Red, Color::Blue],
+            val.convert::<Vec<Color>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_convert_fidelity() {
+        let val = Value::flt(1e300);
+        assert_eq!(1e300, val.convert::<f64>().unwrap());
+        // Struct and Map survive a conversion into Value
+        let val: Value = from_str(r#"[{"a" : 1} (a : 1)]"#).unwrap();
+        assert_eq!(val, val.convert::<Value>().unwrap());
+        assert!(val[0].is_map());
+        assert!(val[1].is_struct());
+    }
+
+    #[test]
+    fn test_from() {
+        assert_eq!(Value::int(3), 3i64.into());
+        assert_eq!(Value::flt(0.5), 0.5.into());
+        assert_eq!(Value::bool(true), true.into());
+        assert_eq!(Value::string("a"), String::from("a").into());
+        assert_eq!(Value::string("a"), "a".into());
+        assert_eq!(Value::Seq(vec![Value::null()]), vec![Value::null()].into());
+        let map = HashMap::from([("a".to_string(), Value::int(1))]);
+        assert_eq!(Value::Map(map.clone()), map.into());
+        assert_eq!(Value::int(1), Some(1i64).into());
+        assert_eq!(Value::null(), None::<i64>.into());
+        assert_eq!(Value::null(), None::<Option<i64>>.into());
+        let seq: Value = (1i64..3).map(Value::from).collect();
+        assert_eq!(Value::Seq(vec![Value::int(1), Value::int(2)]), seq);
+    }
+
+    #[test]
+    fn test_from_nested() {
+        let val: Value = HashMap::from([
+            ("name".to_string(), "jacl".into()),
+            (
+                "tags".to_string(),
+                vec!["a".into(), None::<bool>.into()].into(),
+            ),
+        ])
+        .into();
+        assert_eq!(
+            val,
+            from_str::<Value>(r#"{"name" : "jacl" "tags" : ["a" null]}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut val: Value =
+            from_str(r#"(server : (host : "localhost" port : 80) tags : ["a" "b"] debug : false)"#)
+                .unwrap();
+        val.merge(
+            from_str(r#"(server : (port : 8080) tags : ["c"] debug : true name : "x")"#).unwrap(),
+        );
+        let expected: Value = from_str(
+            r#"(server : (host : "localhost" port : 8080) tags : ["c"] debug : true name : "x")"#,
+        )
+        .unwrap();
+        assert_eq!(expected, val);
+    }
+
+    #[test]
+    fn test_merge_replace() {
+        // incompatible variants are replaced
+        let mut val = Value::Seq(vec![Value::int(1)]);
+        val.merge(Value::int(2));
+        assert_eq!(Value::int(2), val);
+        let mut val = Value::int(1);
+        val.merge(jacl!({ "a": 1 }));
+        assert_eq!(jacl!({ "a": 1 }), val);
+        // a Map merged with a Struct stays a Map
+        let mut val = jacl!({ "a": 1 });
+        val.merge(from_str("(b : 2)").unwrap());
+        assert_eq!(jacl!({ "a": 1, "b": 2 }), val);
+    }
+
+    #[test]
+    fn test_pointer() {
+        let val: Value = from_str(r#"server : (host : "a" ports : [80 443])"#).unwrap();
+        assert_eq!(Some(&val), val.pointer(""));
+        assert_eq!(Some(&Value::string("a")), val.pointer("/server/host"));
+        assert_eq!(Some(&Value::int(443)), val.pointer("/server/ports/1"));
+        // misses
+        assert_eq!(None, val.pointer("server"));
+        assert_eq!(None, val.pointer("/client"));
+        assert_eq!(None, val.pointer("/server/ports/2"));
+        assert_eq!(None, val.pointer("/server/ports/x"));
+        assert_eq!(None, val.pointer("/server/host/0"));
+    }
+
+    #[test]
+    fn test_pointer_escapes() {
+        let val = jacl!({ "a/b": 1, "~": 2, "0": 3 });
+        assert_eq!(Some(&Value::int(1)), val.pointer("/a~1b"));
+        assert_eq!(Some(&Value::int(2)), val.pointer("/~0"));
+        assert_eq!(Some(&Value::int(3)), val.pointer("/0"));
+    }
+
+    #[test]
+    fn test_pointer_mut() {
+        let mut val = jacl!({ "a": [1, { "b": 2 }] });
+        *val.pointer_mut("/a/1/b").unwrap() = Value::int(3);
+        assert_eq!(jacl!({ "a": [1, { "b": 3 }] }), val);
+        assert_eq!(None, val.pointer_mut("/a/2"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        let val: Value = from_str(
+            r#"(name : "jacl" big : 18446744073709551615 neg : -1 pi : 1.5 ok : true none : null
+                tags : ["a" 2] inner : {"x" : 1})"#,
+        )
+        .unwrap();
+        let json: serde_json::Value = val.clone().into();
+        assert_eq!(
+            serde_json::json!({
+                "name": "jacl",
+                "big": 18446744073709551615u64,
+                "neg": -1,
+                "pi": 1.5,
+                "ok": true,
+                "none": null,
+                "tags": ["a", 2],
+                "inner": { "x": 1 },
+            }),
+            json
+        );
+        // objects come back as maps
+        let mut expected = val;
+        if let Value::Struct(map) = expected {
+            expected = Value::Map(map);
+        }
+        assert_eq!(expected, Value::try_from(json).unwrap());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_numbers() {
+        let json = serde_json::json!([1, -1, 1.0, 9223372036854775808u64]);
+        let expected = Value::Seq(vec![
+            Value::int(1),
+            Value::int(-1),
+            Value::flt(1.0),
+            Value::Literal(Literal::Number(Number::UInt(9223372036854775808))),
+        ]);
+        assert_eq!(expected, Value::try_from(json).unwrap());
+        assert_eq!(
+            serde_json::Value::Null,
+            serde_json::Value::from(Value::flt(f64::NAN))
+        );
+    }
+
+    #[test]
+    fn test_literal_casts() {
+        assert_eq!(Some(3), Literal::from_int(3).as_i64());
+        assert_eq!(Some(3), Literal::from_flt(3.0).as_i64());
+        assert_eq!(None, Literal::from_flt(3.5).as_i64());
+        assert_eq!(None, Literal::Number(Number::UInt(u64::MAX)).as_i64());
+        assert_eq!(Some(3.0), Literal::from_int(3).as_f64());
+        assert_eq!(Some(2.5), Literal::from_flt(2.5).as_f64());
+        assert_eq!(None, Literal::from_int(i64::MAX - 1).as_f64());
+        assert_eq!(Some(true), Literal::from_bool(true).as_bool());
+        assert_eq!(Some("a"), Literal::from_string("a").as_str());
+        assert_eq!(None, Literal::from_string("1").as_i64());
+        assert_eq!(None, Literal::Null.as_bool());
+        assert_eq!(None, Literal::from_int(1).as_str());
+
+        assert_eq!(7, Literal::from_int(7).to_u32().unwrap());
+        assert_eq!(7, Literal::from_flt(7.0).to_u32().unwrap());
+        assert_eq!(
+            u32::MAX,
+            Literal::from_int(u32::MAX as i64).to_u32().unwrap()
+        );
+        assert!(Literal::from_int(-1).to_u32().is_err());
+        assert!(Literal::from_int(u32::MAX as i64 + 1).to_u32().is_err());
+        assert!(Literal::from_flt(1.5).to_u32().is_err());
+        assert!(Literal::from_string("1").to_u32().is_err());
+
+        assert_eq!(-7, Literal::from_int(-7).to_i32().unwrap());
+        assert!(Literal::from_int(i32::MIN as i64 - 1).to_i32().is_err());
+        assert!(Literal::from_int(i32::MAX as i64 + 1).to_i32().is_err());
+        assert!(Literal::from_flt(-0.5).to_i32().is_err());
+        assert!(Literal::Null.to_i32().is_err());
+    }
+
+    #[test]
+    fn test_get() {
+        let mut value: Value = from_str(r#"(a : 1 b : [x y] c : {"d" : null})"#).unwrap();
+        assert_eq!(Some(&Value::int(1)), value.get("a"));
+        assert_eq!(None, value.get("z"));
+        assert_eq!(Some(&Value::null()), value["c"].get("d"));
+        assert_eq!(None, value["a"].get("a"));
+        assert_eq!(Some(&Value::string("y")), value["b"].get_index(1));
+        assert_eq!(None, value["b"].get_index(2));
+        assert_eq!(None, value.get_index(0));
+
+        *value.get_mut("a").unwrap() = Value::int(2);
+        assert_eq!(Value::int(2), value["a"]);
+        assert!(value.get_mut("z").is_none());
+
+        assert_eq!(Some(Value::int(2)), value.take("a"));
+        assert_eq!(None, value.get("a"));
+        assert_eq!(2, value.as_map().unwrap().len());
+        assert_eq!(None, value.take("a"));
+        assert_eq!(None, value["b"].take("a"));
+    }
+
+    #[test]
+    fn test_parse() {
+        let value = r#"name : "jacl" ports : [80 443] tags : {"a" : true}"#
+            .parse::<Value>()
+            .unwrap();
+        assert_eq!(Value::string("jacl"), value["name"]);
+        assert_eq!(Value::int(443), value["ports"][1]);
+        let s = value.to_jacl_string().unwrap();
+        assert_eq!(value["ports"], s.parse::<Value>().unwrap()["ports"]);
+        assert_eq!(value["tags"], s.parse::<Value>().unwrap()["tags"]);
+
+        let value = "[1 2.5 null]".parse::<Value>().unwrap();
+        assert_eq!("[1 2.5 null]", value.to_jacl_string().unwrap());
+        assert!("[1".parse::<Value>().is_err());
+    }
+
+    #[test]
+    fn test_to_pretty_jacl() {
+        let value: Value = r#"
+            name : "jacl"
+            ports : [80 443]
+            servers : [(host : "a" "max-conns" : 1.0) (host : "b\n" "max-conns" : 2.5)]
+            tags : {"b" : true "a" : null}
+            empty : (seq : [] map : {} nested : ())
+        "#
+        .parse()
+        .unwrap();
+        let expected = r#"(
+  empty : (
+    map : {}
+    nested : ()
+    seq : []
+  )
+  name : "jacl"
+  ports : [80 443]
+  servers : [
+    (
+      host : "a"
+      "max-conns" : 1.0
+    )
+    (
+      host : "b\n"
+      "max-conns" : 2.5
+    )
+  ]
+  tags : {
+    "a" : null
+    "b" : true
+  }
+)"#;
+        let pretty = value.to_pretty_jacl(2);
+        assert_eq!(expected, pretty);
+        let parsed = crate::de::from_str::<Value>(&pretty).unwrap();
+        assert_eq!(value, parsed);
+        assert!(matches!(
+            parsed["servers"][0]["max-conns"],
+            Value::Literal(Literal::Number(Number::Flt(_)))
+        ));
+        assert!(parsed["tags"].is_map() && parsed["empty"].is_struct());
+
+        assert_eq!(
+            "[\n    [1]\n]",
+            Value::Seq(vec![Value::Seq(vec![Value::int(1)])]).to_pretty_jacl(4)
+        );
+        assert_eq!("\"a\"", Value::string("a").to_pretty_jacl(2));
+    }
+
+    #[test]
+    fn test_accessors() {
+        assert_eq!(Some("a"), Value::string("a").as_str());
+        assert_eq!(None, Value::int(1).as_str());
+        assert_eq!(Some(1), Value::int(1).as_i64());
+        assert_eq!(Some(1), Value::flt(1.0).as_i64());
+        assert_eq!(None, Value::flt(1.5).as_i64());
+        assert_eq!(None, Value::string("1").as_i64());
+        assert_eq!(Some(1.5), Value::flt(1.5).as_f64());
+        assert_eq!(Some(2.0), Value::int(2).as_f64());
+        assert_eq!(None, Value::int(i64::MAX - 1).as_f64());
+        assert_eq!(None, Value::bool(true).as_f64());
+        assert_eq!(Some(true), Value::bool(true).as_bool());
+        assert_eq!(None, Value::null().as_bool());
+
+        let map = HashMap::from([("a".to_string(), Value::int(0))]);
+        assert_eq!(Some(&map), Value::Map(map.clone()).as_map());
+        assert_eq!(Some(&map), Value::Struct(map.clone()).as_map());
+        assert_eq!(None, Value::Seq(vec![]).as_map());
+        assert_eq!(Some(&vec![]), Value::Seq(vec![]).as_seq());
+        assert_eq!(None, Value::Map(map).as_seq());
+    }
+
+    #[test]
+    fn test_type_checks() {
+        assert!(Value::null().is_null());
+        assert!(!Value::int(0).is_null());
+        assert!(Value::int(0).is_number());
+        assert!(Value::flt(0.0).is_number());
+        assert!(!Value::string("0").is_number());
+        assert!(Value::string("").is_string());
+        assert!(!Value::null().is_string());
+        assert!(Value::bool(false).is_bool());
+        assert!(!Value::int(0).is_bool());
+        assert!(Value::Map(HashMap::new()).is_map());
+        assert!(!Value::Struct(HashMap::new()).is_map());
+        assert!(Value::Struct(HashMap::new()).is_struct());
+        assert!(!Value::Map(HashMap::new()).is_struct());
+        assert!(Value::Seq(vec![]).is_seq());
+        assert!(!Value::null().is_seq());
+    }
+
+    #[test]
+    fn test_index() {
+        let val: Value = from_str(r#"{"a" : [1 (b : true)] "c" : null}"#).unwrap();
+        assert_eq!(Value::int(1), val["a"][0]);
+        assert_eq!(Value::bool(true), val["a"][1]["b"]);
+        // misses
+        assert_eq!(Value::null(), val["missing"]);
+        assert_eq!(Value::null(), val["a"][2]);
+        // wrong variant
+        assert_eq!(Value::null(), val[0]);
+        assert_eq!(Value::null(), val["a"]["b"]);
+        assert_eq!(Value::null(), val["a"][0]["b"]);
+    }
+
+    #[test]
+    fn test_index_mut() {
+        let mut val = Value::null();
+        val["a"]["b"] = Value::int(1);
+        val["c"] = Value::Seq(vec![Value::null()]);
+        val["c"][0] = Value::bool(false);
+        let inner = Value::Map(HashMap::from([("b".into(), Value::int(1))]));
+        let expected = Value::Map(HashMap::from([
+            ("a".into(), inner),
+            ("c".into(), Value::Seq(vec![Value::bool(false)])),
         ]));
-        assert_eq!(val, from_str("\"a\" : 0").unwrap());
+        assert_eq!(expected, val);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_mut_wrong_variant() {
+        let mut val = Value::int(0);
+        val["a"] = Value::null();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_mut_out_of_bounds() {
+        let mut val = Value::Seq(vec![]);
+        val[0] = Value::null();
+    }
+
+    #[test]
+    fn test_value_bare_string() {
+        let val = Value::Struct(HashMap::from([("color".into(), Value::string("red"))]));
+        assert_eq!(val, from_str("color : red").unwrap());
+        assert_eq!(val, from_str("(color : red)").unwrap());
+        // keywords win only when they are the whole word
+        let val: Value = from_str("[true false null trueish nullable]").unwrap();
+        let expected = Value::Seq(vec![
+            Value::bool(true),
+            Value::bool(false),
+            Value::null(),
+            Value::string("trueish"),
+            Value::string("nullable"),
+        ]);
+        assert_eq!(expected, val);
     }
 }
@@ -0,0 +1,109 @@
+//! Reading points in time: [`SystemTime`]s from unix timestamps, and with
+//! the `chrono` feature, [`DateTime<Utc>`](chrono::DateTime)s from RFC 3339
+//! strings like `"2024-01-02T03:04:05Z"`.
+//!
+//! ```
+//! use serde::Deserialize;
+//! use std::time::{Duration, SystemTime, UNIX_EPOCH};
+//!
+//! #[derive(Deserialize)]
+//! struct Build {
+//!     #[serde(deserialize_with = "serde_jacl::time::unix_seconds")]
+//!     started: SystemTime,
+//! }
+//!
+//! let build: Build = serde_jacl::de::from_str("started : 1700000000").unwrap();
+//! assert_eq!(UNIX_EPOCH + Duration::from_secs(1_700_000_000), build.started);
+//! ```
+
+use serde::{Deserialize, Deserializer};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Deserializes a [`SystemTime`] from a whole number of seconds since the
+/// unix epoch, for use with
+/// `#[serde(deserialize_with = "serde_jacl::time::unix_seconds")]`.
+pub fn unix_seconds<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let secs = u64::deserialize(deserializer)?;
+    Ok(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Deserializes a [`DateTime<Utc>`](chrono::DateTime) from an RFC 3339
+/// string, for use with
+/// `#[serde(deserialize_with = "serde_jacl::time::rfc3339")]`. Timestamps
+/// with an offset, like `"2024-01-02T05:04:05+02:00"`, are converted to UTC.
+///
+/// ```
+/// use chrono::{DateTime, TimeZone, Utc};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Release {
+///     #[serde(deserialize_with = "serde_jacl::time::rfc3339")]
+///     date: DateTime<Utc>,
+/// }
+///
+/// let release: Release = serde_jacl::de::from_str(r#"date : "2024-01-02T03:04:05Z""#).unwrap();
+/// assert_eq!(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap(), release.date);
+/// ```
+#[cfg(feature = "chrono")]
+pub fn rfc3339<'de, D>(deserializer: D) -> Result<chrono::DateTime<chrono::Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    chrono::DateTime::parse_from_rfc3339(&s)
+        .map(|date| date.with_timezone(&chrono::Utc))
+        .map_err(|err| serde::de::Error::custom(format!("invalid timestamp \"{}\": {}", s, err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::from_str;
+
+    #[test]
+    fn test_unix_seconds() {
+        #[derive(Deserialize, Debug)]
+        struct Build {
+            #[serde(deserialize_with = "unix_seconds")]
+            started: SystemTime,
+        }
+        let build = from_str::<Build>("started : 1700000000").unwrap();
+        assert_eq!(
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            build.started
+        );
+        let err = from_str::<Build>("started : -1").unwrap_err();
+        assert_eq!((1, 10), (err.line(), err.col()));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_rfc3339() {
+        use crate::de::ErrorKind;
+        use chrono::{DateTime, TimeZone, Utc};
+
+        #[derive(Deserialize, Debug)]
+        struct Release {
+            #[serde(deserialize_with = "rfc3339")]
+            date: DateTime<Utc>,
+        }
+        let expected = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let release = from_str::<Release>(r#"date : "2024-01-02T03:04:05Z""#).unwrap();
+        assert_eq!(expected, release.date);
+        let release = from_str::<Release>(r#"date : "2024-01-02T05:04:05+02:00""#).unwrap();
+        assert_eq!(expected, release.date);
+
+        let err = from_str::<Release>("// released\ndate : \"2024-13-02T03:04:05Z\"").unwrap_err();
+        assert_eq!((2, 7), (err.line(), err.col()));
+        assert_eq!(
+            &ErrorKind::Custom(
+                "invalid timestamp \"2024-13-02T03:04:05Z\": input is out of range".into()
+            ),
+            err.kind()
+        );
+    }
+}